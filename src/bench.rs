@@ -0,0 +1,156 @@
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use reqwest::{Client, Method};
+use serde_json::Value;
+use tokio::task::JoinSet;
+
+pub struct BenchArgs {
+    pub method: Method,
+    pub service: String,
+    pub url: String,
+    pub payload: Option<Value>,
+    pub requests: usize,
+    pub concurrency: usize,
+}
+
+pub struct BenchSummary {
+    pub method: String,
+    pub service: String,
+    pub url: String,
+    pub requests: usize,
+    pub concurrency: usize,
+    pub duration: Duration,
+    pub throughput: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub statuses: BTreeMap<u16, usize>,
+    pub errors: BTreeMap<String, usize>,
+}
+
+struct Sample {
+    elapsed: Duration,
+    outcome: Result<u16, String>,
+}
+
+fn error_kind(err: &reqwest::Error) -> String {
+    if err.is_timeout() {
+        "timeout".into()
+    } else if err.is_connect() {
+        "connect".into()
+    } else if err.is_body() || err.is_decode() {
+        "body".into()
+    } else if err.is_request() {
+        "request".into()
+    } else {
+        "other".into()
+    }
+}
+
+async fn send_one(client: &Client, args: &BenchArgs) -> Sample {
+    let mut req_builder = client.request(args.method.clone(), args.url.clone());
+    if let Some(json) = &args.payload {
+        req_builder = req_builder.json(json);
+    }
+
+    let started = Instant::now();
+    let outcome = match req_builder.send().await {
+        // read the body too, otherwise slow responses look a lot faster than they are
+        Ok(res) => {
+            let status = res.status().as_u16();
+            match res.bytes().await {
+                Ok(_) => Ok(status),
+                Err(err) => Err(error_kind(&err)),
+            }
+        }
+        Err(err) => Err(error_kind(&err)),
+    };
+
+    Sample {
+        elapsed: started.elapsed(),
+        outcome,
+    }
+}
+
+/// Nearest-rank percentile over already sorted latencies.
+fn percentile_ms(sorted: &[Duration], pct: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = ((pct / 100.0) * sorted.len() as f64).ceil() as usize;
+    let idx = rank.clamp(1, sorted.len()) - 1;
+    sorted[idx].as_secs_f64() * 1000.0
+}
+
+pub async fn run(client: Client, args: BenchArgs) -> BenchSummary {
+    let args = Arc::new(args);
+    let next_request = Arc::new(AtomicUsize::new(0));
+    let workers = args.concurrency.clamp(1, args.requests.max(1));
+
+    let started = Instant::now();
+    let mut set = JoinSet::new();
+    for _ in 0..workers {
+        let client = client.clone();
+        let args = Arc::clone(&args);
+        let next_request = Arc::clone(&next_request);
+        set.spawn(async move {
+            let mut samples = Vec::new();
+            while next_request.fetch_add(1, Ordering::Relaxed) < args.requests {
+                samples.push(send_one(&client, &args).await);
+            }
+            samples
+        });
+    }
+
+    let mut samples = Vec::with_capacity(args.requests);
+    while let Some(joined) = set.join_next().await {
+        match joined {
+            Ok(mut worker_samples) => samples.append(&mut worker_samples),
+            Err(err) => eprintln!("Bench worker died: {}", err),
+        }
+    }
+    let duration = started.elapsed();
+
+    let mut statuses = BTreeMap::new();
+    let mut errors = BTreeMap::new();
+    let mut latencies = Vec::with_capacity(samples.len());
+    for sample in samples {
+        match sample.outcome {
+            Ok(status) => {
+                *statuses.entry(status).or_insert(0) += 1;
+                latencies.push(sample.elapsed);
+            }
+            Err(kind) => *errors.entry(kind).or_insert(0) += 1,
+        }
+    }
+    latencies.sort();
+
+    let completed = latencies.len() + errors.values().sum::<usize>();
+    let throughput = if duration.is_zero() {
+        0.0
+    } else {
+        completed as f64 / duration.as_secs_f64()
+    };
+
+    BenchSummary {
+        method: args.method.to_string(),
+        service: args.service.clone(),
+        url: args.url.clone(),
+        requests: completed,
+        concurrency: workers,
+        duration,
+        throughput,
+        p50_ms: percentile_ms(&latencies, 50.0),
+        p90_ms: percentile_ms(&latencies, 90.0),
+        p99_ms: percentile_ms(&latencies, 99.0),
+        statuses,
+        errors,
+    }
+}
@@ -1,8 +1,9 @@
-use config::Config;
+use config::{Config, Value};
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
 
 pub struct Cfg {
-    opts: HashMap<String, String>,
+    opts: HashMap<String, Value>,
 }
 
 impl Cfg {
@@ -34,12 +35,51 @@ impl Cfg {
 
     /// this defaults to an empty string if no value is found from config file
     pub fn get(&self, key_name: &str) -> String {
-        match self.opts.get(key_name) {
-            Some(value) => value.to_string(),
+        match self.opts.get(key_name).cloned().map(Value::into_string) {
+            Some(Ok(value)) => value,
+            Some(Err(err)) => {
+                eprintln!("Config key {} isn't a plain value: {}", key_name, err);
+                String::new()
+            }
             None => {
                 eprintln!("No value associated with config key {}", key_name);
                 String::new()
             }
         }
     }
+
+    /// Reads a whole `[section]` from the config file, `None` if the section doesn't exist.
+    pub fn get_section<T: DeserializeOwned>(&self, key_name: &str) -> Option<T> {
+        let value = self.opts.get(key_name)?.clone();
+        match value.try_deserialize() {
+            Ok(section) => Some(section),
+            Err(err) => {
+                eprintln!(
+                    "Couldn't deserialise config section [{}]: {}",
+                    key_name, err
+                );
+                std::process::exit(1)
+            }
+        }
+    }
+
+    /// Reads `[services.<service>.<section>]`, used for per service overrides of global sections.
+    pub fn get_service_section<T: DeserializeOwned>(
+        &self,
+        service: &str,
+        key_name: &str,
+    ) -> Option<T> {
+        let services: HashMap<String, HashMap<String, Value>> = self.get_section("services")?;
+        let value = services.get(service)?.get(key_name)?.clone();
+        match value.try_deserialize() {
+            Ok(section) => Some(section),
+            Err(err) => {
+                eprintln!(
+                    "Couldn't deserialise config section [services.{}.{}]: {}",
+                    service, key_name, err
+                );
+                std::process::exit(1)
+            }
+        }
+    }
 }
@@ -1,5 +1,10 @@
-use clap::{Args, Parser, Subcommand, ValueEnum};
+use clap::{ArgAction, Args, Parser, Subcommand, ValueEnum};
 use reqwest::Method;
+use std::{fmt::Display, str::FromStr};
+
+use crate::retention::parse_age;
+use crate::select::Selector;
+use crate::workspace::parse_workspace;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub enum ServerEnv {
@@ -18,11 +23,87 @@ impl AsRef<str> for ServerEnv {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Status, headers and coloured json body
+    Pretty,
+    /// Only the json body
+    Body,
+    /// Body exactly as received
+    Raw,
+    /// Status line and headers
+    Headers,
+    /// Only the status code
+    Status,
+    /// Machine readable envelope with status, headers, body and timing
+    Json,
+    /// Nothing, the request is still stored
+    None,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// One json object per request and line
+    Jsonl,
+}
+
+/// How redirects are handled, parsed from `follow`, `none` or a maximum hop count.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Redirects {
+    Follow,
+    None,
+    Max(usize),
+}
+
+impl FromStr for Redirects {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "follow" => Ok(Redirects::Follow),
+            "none" => Ok(Redirects::None),
+            other => other.parse().map(Redirects::Max).map_err(|_| {
+                format!("expected `follow`, `none` or a number of hops, got `{other}`")
+            }),
+        }
+    }
+}
+
+impl Display for Redirects {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Redirects::Follow => write!(f, "follow"),
+            Redirects::None => write!(f, "none"),
+            Redirects::Max(hops) => write!(f, "max {hops}"),
+        }
+    }
+}
+
+/// Tags end up in `tag:` filters that are split on whitespace, so they can't contain any.
+fn parse_tag(s: &str) -> Result<String, String> {
+    let tag = s.trim();
+    if tag.is_empty() {
+        return Err("tag can't be empty".into());
+    }
+    if tag.contains(char::is_whitespace) {
+        return Err(format!("tag `{tag}` can't contain spaces"));
+    }
+    Ok(tag.to_string())
+}
+
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct ScoobyArgs {
     #[clap(subcommand)]
     pub mode_type: ModeType,
+
+    /// Database file to use instead of ~/.scooby/dooby.db, also read from SCOOBY_DB
+    #[arg(long, global = true, value_name = "PATH", conflicts_with = "workspace")]
+    pub db: Option<String>,
+
+    /// Named history kept apart from the others, stored in ~/.scooby/workspaces/<name>.db
+    #[arg(short('w'), long, global = true, value_name = "NAME", value_parser = parse_workspace)]
+    pub workspace: Option<String>,
 }
 
 #[derive(Debug, Subcommand)]
@@ -33,15 +114,13 @@ pub enum ModeType {
     /// Query, view and export previous requests.
     #[clap(subcommand)]
     Db(DbCommand),
+
+    /// Fire a batch of requests at a route and report throughput and latency
+    Bench(BenchCommand),
 }
 
 #[derive(Debug, Args)]
-pub struct ReqCommand {
-    // for now, use the simple version
-    /// HTTP Method
-    #[arg(value_enum)]
-    pub method: Method, // todo: this might just be a subcommand, so we can have separate args for different methods.
-
+pub struct TargetArgs {
     /// Target service. Intended use is to use an abbreviation which is linked to a
     /// value in `config.toml`: `scooby req GET <my-abbr> some-resource/some-id`.
     /// config.toml: my-abbr = "some-longer-part-of-url"
@@ -67,6 +146,60 @@ pub struct ReqCommand {
     /// Querystring parameters
     #[arg(short, long)]
     pub qsp: Option<String>,
+}
+
+/// Http client options, these override `[http]` and `[services.<service>.http]` from config
+#[derive(Debug, Args)]
+pub struct HttpArgs {
+    /// Total request timeout in seconds
+    #[arg(long("timeout"), value_name("SECS"))]
+    pub timeout_secs: Option<u64>,
+
+    /// Connection timeout in seconds
+    #[arg(long("connect-timeout"), value_name("SECS"))]
+    pub connect_timeout_secs: Option<u64>,
+
+    /// Redirect policy: `follow`, `none` or a maximum number of hops
+    #[arg(long("redirects"), value_name("POLICY"))]
+    pub redirects: Option<Redirects>,
+
+    /// Proxy used for both http and https traffic
+    #[arg(long("proxy"), value_name("URL"))]
+    pub proxy: Option<String>,
+
+    /// Custom CA bundle (PEM) to trust on top of the system roots
+    #[arg(long("cacert"), value_name("PATH"))]
+    pub ca_bundle: Option<String>,
+
+    /// Client certificate (PEM), requires `--key`
+    #[arg(long("cert"), value_name("PATH"), requires("client_key"))]
+    pub client_cert: Option<String>,
+
+    /// Private key (PKCS#8 PEM) for the client certificate
+    #[arg(long("key"), value_name("PATH"), requires("client_cert"))]
+    pub client_key: Option<String>,
+
+    /// Accept invalid TLS certificates and host names, only use against your own dev stacks.
+    /// `--insecure=false` turns it off when the config has it on
+    #[arg(
+        long,
+        value_name("BOOL"),
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("true")
+    )]
+    pub insecure: Option<bool>,
+}
+
+#[derive(Debug, Args)]
+pub struct ReqCommand {
+    // for now, use the simple version
+    /// HTTP Method
+    #[arg(value_enum)]
+    pub method: Method, // todo: this might just be a subcommand, so we can have separate args for different methods.
+
+    #[command(flatten)]
+    pub target: TargetArgs,
 
     /// Where to look for json payload
     #[arg(
@@ -76,12 +209,75 @@ pub struct ReqCommand {
         required_if_eq("method", "PATCH")
     )]
     pub payload_path: Option<String>,
+
+    /// How many times to retry on retryable failures, overrides `[retry]` from config
+    #[arg(long("retry"), value_name("COUNT"))]
+    pub retry: Option<u32>,
+
+    #[command(flatten)]
+    pub http: HttpArgs,
+
+    /// What to print of the response, colours are turned off when stdout isn't a terminal
+    #[arg(short('o'), long("output"), value_enum, default_value = "pretty")]
+    pub output: OutputFormat,
+
+    /// Print only the parts of the response body matching a jq-like expression,
+    /// e.g. `.items[0].id`, `.items[?(.status == "open")].id` or `.items | length`
+    #[arg(long("select"), value_name("EXPR"), conflicts_with("output"))]
+    pub select: Option<Selector>,
+
+    /// Verbose output to stderr. `-v` prints how the url was built and the effective http
+    /// settings, `-vv` also prints the request line, headers and body, and the response headers
+    #[arg(short('v'), long("verbose"), action(ArgAction::Count))]
+    pub verbose: u8,
+
+    /// Tag the stored request, e.g. a ticket like `PAY-123`. Repeat for more tags
+    #[arg(long("tag"), value_name("TAG"), value_parser(parse_tag))]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct BenchCommand {
+    /// HTTP Method
+    #[arg(value_enum)]
+    pub method: Method,
+
+    #[command(flatten)]
+    pub target: TargetArgs,
+
+    /// Where to look for json payload, sent with every request
+    #[arg(
+        short('p'),
+        long("payload"),
+        required_if_eq("method", "POST"),
+        required_if_eq("method", "PATCH")
+    )]
+    pub payload_path: Option<String>,
+
+    /// Total number of requests to send
+    #[arg(short('n'), long("requests"), default_value_t = 100)]
+    pub requests: usize,
+
+    /// How many requests are in flight at the same time
+    #[arg(short('c'), long("concurrency"), default_value_t = 10)]
+    pub concurrency: usize,
+
+    /// Store a summary row of the run in the db
+    #[arg(long)]
+    pub store: bool,
+
+    #[command(flatten)]
+    pub http: HttpArgs,
 }
 
 #[derive(Debug, Args)]
 pub struct ListAllCommand {
     #[arg()]
     pub time_range: String,
+
+    /// Only requests with this tag, repeat to require several
+    #[arg(long("tag"), value_name("TAG"), value_parser(parse_tag))]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Args)]
@@ -90,12 +286,113 @@ pub struct ListByServiceCommand {
     pub service: String,
     #[arg()]
     pub time_range: String,
+
+    /// Only requests with this tag, repeat to require several
+    #[arg(long("tag"), value_name("TAG"), value_parser(parse_tag))]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ShowCommand {
+    /// Id of the stored request
+    #[arg()]
+    pub id: i64,
+
+    /// Print only the parts of the stored response matching a jq-like expression
+    #[arg(long("select"), value_name("EXPR"))]
+    pub select: Option<Selector>,
+}
+
+#[derive(Debug, Args)]
+pub struct DeleteCommand {
+    /// Id of the stored request, or a filter like the one in the TUI:
+    /// "service:user status:5xx from:2025-01-01 to:2025-01-31 method:get pinned:no"
+    #[arg()]
+    pub target: String,
+
+    /// Delete pinned requests too, they're skipped otherwise
+    #[arg(long)]
+    pub include_pinned: bool,
+
+    /// Don't ask before deleting by filter
+    #[arg(short('y'), long)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct PinCommand {
+    /// Id of the stored request
+    #[arg()]
+    pub id: i64,
+}
+
+#[derive(Debug, Args)]
+pub struct NoteCommand {
+    /// Id of the stored request
+    #[arg()]
+    pub id: i64,
+
+    /// The note, e.g. "repro for the checkout bug"
+    #[arg()]
+    pub text: String,
+}
+
+#[derive(Debug, Args)]
+pub struct PruneCommand {
+    /// Delete requests older than this, e.g. `30d`, `12h` or `2w`. Without it the limits from
+    /// `[retention]` in the config are applied
+    #[arg(long, value_name("AGE"), value_parser(parse_age))]
+    pub older_than: Option<chrono::Duration>,
+
+    /// Don't delete pinned requests
+    #[arg(long)]
+    pub keep_pinned: bool,
+
+    /// Don't ask before deleting
+    #[arg(short('y'), long)]
+    pub yes: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct TagCommand {
+    /// Id of the stored request
+    #[arg()]
+    pub id: i64,
+
+    /// e.g. a ticket like `PAY-123`
+    #[arg(value_parser(parse_tag))]
+    pub tag: String,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportCommand {
+    #[arg(short('f'), long, value_enum, default_value = "jsonl")]
+    pub format: ExportFormat,
+
+    /// Write to this file instead of stdout
+    #[arg(short('o'), long, value_name("FILE"))]
+    pub output: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct ImportCommand {
+    /// File written by `db export`, `-` reads stdin
+    #[arg()]
+    pub file: String,
 }
 
 #[derive(Debug, Args)]
 /// By default should render a TUI with all requests made in a list, that is browseable
 /// Supports an extra mode where user can raw dog turso with queries.
-pub struct UiCommand {}
+pub struct UiCommand {
+    /// Jump to new requests as they're stored, toggle with `F` in the ui
+    #[arg(long)]
+    pub follow: bool,
+
+    /// Used when resending requests from the composer
+    #[command(flatten)]
+    pub http: HttpArgs,
+}
 
 #[derive(Debug, Subcommand)]
 pub enum DbCommand {
@@ -105,6 +402,51 @@ pub enum DbCommand {
     /// List all requests made to a specific service
     ListByService(ListByServiceCommand),
 
+    /// Show a single stored request
+    Show(ShowCommand),
+
+    /// Delete a stored request by id, or every request matching a filter
+    Delete(DeleteCommand),
+
+    /// Pin a request so it stands out and survives filtered deletes
+    Pin(PinCommand),
+
+    /// Unpin a request
+    Unpin(PinCommand),
+
+    /// Attach a note to a request, an empty note removes it
+    Note(NoteCommand),
+
+    /// Tag a request, to group everything that belongs to a ticket or investigation
+    Tag(TagCommand),
+
+    /// Remove a tag from a request
+    Untag(TagCommand),
+
+    /// Delete old requests, by age or by the `[retention]` limits from the config
+    Prune(PruneCommand),
+
+    /// Row counts, size by service and the oldest entry
+    Stats,
+
+    /// Encrypt requests stored before `[encryption]` was turned on
+    Encrypt,
+
+    /// Print a new random key for `[encryption]`
+    Keygen,
+
+    /// List the named workspaces and which one is in use
+    Workspaces,
+
+    /// Push new requests to the `[remote]` libSQL database and pull everyone else's
+    Sync,
+
+    /// Write the whole history out, for backups or moving it to another machine
+    Export(ExportCommand),
+
+    /// Read an export back in, requests that are already stored are skipped
+    Import(ImportCommand),
+
     /// Render a TUI (powered by ratatui) to browse and search the stored data
     Ui(UiCommand),
 }
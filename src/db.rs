@@ -1,13 +1,27 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Days, NaiveDate, Utc};
 use colored::Colorize;
 use colored_json::to_colored_json_auto;
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
 use serde_json::{Value, from_str};
-use std::{error::Error, fmt::Display, path::Path, time::SystemTime};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt::Display,
+    path::Path,
+    time::{Duration, Instant, SystemTime},
+};
 use tokio::fs;
 use turso::{Builder, Connection, Row};
 
+use crate::bench::BenchSummary;
+use crate::encryption::{self, Cipher, Kdf, is_encrypted};
+use crate::formatting::masked_headers_to_json;
 use crate::http::ResponseParts;
+use crate::redact::redactor;
+use crate::retention::RetentionPolicy;
+use crate::retry::Attempt;
 
 pub struct DbStoreArgs {
     pub method: String,
@@ -15,14 +29,30 @@ pub struct DbStoreArgs {
     pub url: String,
     pub route_url: String,
     pub payload: Option<serde_json::Value>,
+    pub attempts: Vec<Attempt>,
+    pub server_env: Option<String>,
+    pub qsp: Option<String>,
+    /// what was actually sent, client defaults included, secrets get masked before storing
+    pub request_headers: HeaderMap,
+    pub tags: Vec<String>,
 }
 
 pub struct Db {
     conn: Connection,
+    path: String,
+    /// set when the db is encrypted or encryption is turned on, bodies and headers go through it
+    cipher: Option<Cipher>,
 }
 
+/// Shown in place of encrypted values the key here can't open.
+const UNREADABLE: &str = "<encrypted>";
+/// Encrypted with the key when encryption is first turned on, a wrong key fails to decrypt it.
+pub const CHECK_TEXT: &str = "scooby dooby doo";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScoobyRequest {
+    /// rowid in `requests`, what `db show` and friends take
+    pub id: i64,
     method: String,
     service: String,
     route_url: String,
@@ -30,29 +60,571 @@ pub struct ScoobyRequest {
     payload_json: Option<Value>,
     response_json: Option<Value>,
     created_at: DateTime<Utc>,
+    attempts: i64,
+    status: Option<u16>,
+    #[serde(default)]
+    server_env: Option<String>,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    user: Option<String>,
+    #[serde(default)]
+    host: Option<String>,
+}
+
+/// Summary columns of a stored request, enough to render a list row without the bodies.
+pub struct RequestSummary {
+    pub id: i64,
+    pub method: String,
+    pub service: String,
+    pub route_url: String,
+    pub status: Option<u16>,
+    pub created_at: DateTime<Utc>,
+    pub pinned: bool,
+    pub note: Option<String>,
+    pub tags: Vec<String>,
 }
 
-pub struct UiDisplayRequest {
-    pub key: String,
-    pub content: Option<Value>,
-    pub response: Option<Value>,
+/// Everything about a stored request that isn't a body, for the TUI metadata pane.
+/// Rows stored before these were recorded have most of it missing.
+pub struct RequestMetadata {
+    pub full_url: String,
+    pub qsp: Option<String>,
+    pub server_env: Option<String>,
+    pub request_headers: Option<Value>,
+    pub status: Option<u16>,
+    pub response_headers: Option<Value>,
+    pub elapsed_ms: Option<i64>,
+    pub attempts: i64,
+    pub created_at: DateTime<Utc>,
+    pub user: Option<String>,
+    pub host: Option<String>,
 }
 
-pub fn to_ui_displayable(data: Vec<ScoobyRequest>) -> Vec<UiDisplayRequest> {
-    data.iter()
-        .map(|item| UiDisplayRequest {
-            key: format!(
-                "{} {} {} {} {}",
-                item.method,
-                item.service,
-                item.route_url,
-                item.created_at.format("%Y-%m-%d %H:%M:%S"),
-                item.route_url,
-            ),
-            content: item.payload_json.clone(),
-            response: item.response_json.clone(),
+/// The columns of a `requests` row that move between databases, in this order. Encrypted
+/// columns stay encrypted and the local id is left behind.
+pub const STORED_COLUMNS: &str = "content_hash, method, service, route_url, full_url, payload,
+response_json, created_at, attempts, status, server_env, qsp, request_headers, response_headers,
+elapsed_ms, pinned, note, user, host";
+
+/// What another db needs to read this one's encrypted values, the `encryption` table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncryptionParams {
+    pub salt: String,
+    pub kdf: String,
+    /// `CHECK_TEXT` encrypted with the key, tells whether a key fits
+    pub check: String,
+}
+
+/// A `requests` row exactly as stored, see `STORED_COLUMNS`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoredRequest {
+    pub content_hash: String,
+    pub method: String,
+    pub service: String,
+    pub route_url: String,
+    pub full_url: String,
+    pub payload: Option<String>,
+    pub response_json: Option<String>,
+    pub created_at: String,
+    pub attempts: i64,
+    pub status: Option<i64>,
+    pub server_env: Option<String>,
+    pub qsp: Option<String>,
+    pub request_headers: Option<String>,
+    pub response_headers: Option<String>,
+    pub elapsed_ms: Option<i64>,
+    pub pinned: bool,
+    pub note: Option<String>,
+    pub user: Option<String>,
+    pub host: Option<String>,
+}
+
+fn value_text(value: turso::Value) -> Result<Option<String>, String> {
+    match value {
+        turso::Value::Null => Ok(None),
+        turso::Value::Text(text) => Ok(Some(text)),
+        other => Err(format!("expected text, got {:?}", other)),
+    }
+}
+
+fn value_int(value: turso::Value) -> Result<Option<i64>, String> {
+    match value {
+        turso::Value::Null => Ok(None),
+        turso::Value::Integer(int) => Ok(Some(int)),
+        other => Err(format!("expected an integer, got {:?}", other)),
+    }
+}
+
+impl StoredRequest {
+    /// From values in `STORED_COLUMNS` order, whichever db they came from.
+    pub fn from_values(values: Vec<turso::Value>) -> Result<Self, String> {
+        let mut values = values.into_iter();
+        let mut next = || values.next().unwrap_or(turso::Value::Null);
+        let required = |value: Option<String>, column: &str| {
+            value.ok_or_else(|| format!("{column} is missing"))
+        };
+        Ok(StoredRequest {
+            content_hash: required(value_text(next())?, "content_hash")?,
+            method: required(value_text(next())?, "method")?,
+            service: required(value_text(next())?, "service")?,
+            route_url: required(value_text(next())?, "route_url")?,
+            full_url: required(value_text(next())?, "full_url")?,
+            payload: value_text(next())?,
+            response_json: value_text(next())?,
+            created_at: required(value_text(next())?, "created_at")?,
+            attempts: value_int(next())?.unwrap_or(1),
+            status: value_int(next())?,
+            server_env: value_text(next())?,
+            qsp: value_text(next())?,
+            request_headers: value_text(next())?,
+            response_headers: value_text(next())?,
+            elapsed_ms: value_int(next())?,
+            pinned: value_int(next())?.unwrap_or(0) != 0,
+            note: value_text(next())?,
+            user: value_text(next())?,
+            host: value_text(next())?,
         })
-        .collect()
+    }
+
+    /// Whether any body or header column is encrypted.
+    pub fn is_encrypted(&self) -> bool {
+        [
+            &self.payload,
+            &self.response_json,
+            &self.request_headers,
+            &self.response_headers,
+        ]
+        .into_iter()
+        .flatten()
+        .any(|value| is_encrypted(value))
+    }
+
+    /// Values in `STORED_COLUMNS` order.
+    pub fn params(&self) -> Vec<turso::Value> {
+        let text = |value: &Option<String>| match value {
+            Some(value) => turso::Value::Text(value.clone()),
+            None => turso::Value::Null,
+        };
+        let int = |value: Option<i64>| match value {
+            Some(value) => turso::Value::Integer(value),
+            None => turso::Value::Null,
+        };
+        vec![
+            turso::Value::Text(self.content_hash.clone()),
+            turso::Value::Text(self.method.clone()),
+            turso::Value::Text(self.service.clone()),
+            turso::Value::Text(self.route_url.clone()),
+            turso::Value::Text(self.full_url.clone()),
+            text(&self.payload),
+            text(&self.response_json),
+            turso::Value::Text(self.created_at.clone()),
+            turso::Value::Integer(self.attempts),
+            int(self.status),
+            text(&self.server_env),
+            text(&self.qsp),
+            text(&self.request_headers),
+            text(&self.response_headers),
+            int(self.elapsed_ms),
+            turso::Value::Integer(self.pinned as i64),
+            text(&self.note),
+            text(&self.user),
+            text(&self.host),
+        ]
+    }
+}
+
+/// `INSERT INTO requests` with a placeholder per `STORED_COLUMNS` column.
+pub fn insert_stored_sql() -> String {
+    let placeholders = vec!["?"; STORED_COLUMNS.split(',').count()].join(", ");
+    format!("INSERT INTO requests ({STORED_COLUMNS}) VALUES ({placeholders})")
+}
+
+/// Identifies a request across databases. Made from the plain bodies, so it's the same whether
+/// or not they're encrypted.
+#[allow(clippy::too_many_arguments)]
+fn content_hash(
+    method: &str,
+    service: &str,
+    full_url: &str,
+    payload: Option<&str>,
+    response_json: Option<&str>,
+    created_at: &str,
+    status: Option<i64>,
+    user: Option<&str>,
+    host: Option<&str>,
+) -> String {
+    let status = status.map(|status| status.to_string());
+    let mut hasher = Sha256::new();
+    for part in [
+        Some(method),
+        Some(service),
+        Some(full_url),
+        payload,
+        response_json,
+        Some(created_at),
+        status.as_deref(),
+        user,
+        host,
+    ] {
+        // length prefixed so moving text from one field to the next changes the hash
+        match part {
+            Some(part) => hasher.update(format!("{}:{}", part.len(), part)),
+            None => hasher.update("-"),
+        }
+    }
+    hex::encode(hasher.finalize())
+}
+
+/// Who's storing the request, `$USER` and the host name.
+fn origin() -> (Option<String>, Option<String>) {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok();
+    let host = std::env::var("HOSTNAME")
+        .ok()
+        .or_else(|| std::fs::read_to_string("/etc/hostname").ok())
+        .map(|host| host.trim().to_string())
+        .filter(|host| !host.is_empty());
+    (user, host)
+}
+
+/// Requests stored for a single service, for `db stats`.
+pub struct ServiceStats {
+    pub service: String,
+    pub requests: i64,
+    /// payloads and responses together
+    pub body_bytes: i64,
+    pub oldest: DateTime<Utc>,
+}
+
+/// What's in the db and how big it got, for `db stats`.
+pub struct DbStats {
+    pub path: String,
+    /// db file plus its write-ahead log, `None` if it couldn't be read
+    pub file_bytes: Option<u64>,
+    pub requests: i64,
+    pub pinned: i64,
+    pub tagged: i64,
+    pub attempts: i64,
+    pub bench_runs: i64,
+    pub oldest: Option<(i64, DateTime<Utc>)>,
+    pub newest: Option<(i64, DateTime<Utc>)>,
+    /// biggest first
+    pub services: Vec<ServiceStats>,
+}
+
+/// Bytes of the stored bodies, LENGTH counts characters on text so it goes through a blob.
+const BODY_BYTES_SQL: &str = "LENGTH(CAST(COALESCE(payload, '') AS BLOB)) + LENGTH(CAST(COALESCE(response_json, '') AS BLOB))";
+
+/// Narrows down which requests are queried, every set field has to match.
+#[derive(Debug, Default, Clone)]
+pub struct EntryFilter {
+    pub method: Option<String>,
+    /// part of the service name
+    pub service: Option<String>,
+    /// inclusive status range, `(404, 404)` or `(500, 599)`
+    pub status: Option<(u16, u16)>,
+    pub from: Option<DateTime<Utc>>,
+    /// exclusive
+    pub to: Option<DateTime<Utc>>,
+    /// only rows newer than this one, used to pick up what was stored since the last look
+    pub after_id: Option<i64>,
+    /// fuzzy, matches when the characters appear in order in id, method, service, route, time
+    /// and note
+    pub search: Option<String>,
+    pub id: Option<i64>,
+    pub pinned: Option<bool>,
+    /// every one of them has to be on the request
+    pub tags: Vec<String>,
+}
+
+/// turso doesn't do `IN (SELECT ...)` in deletes yet, so ids go in as lists of this size at most
+const ID_CHUNK_SIZE: usize = 500;
+
+/// Appends a clause per tag that only lets requests carrying it through.
+fn push_tag_clauses(tags: &[String], clauses: &mut Vec<&str>, params: &mut Vec<turso::Value>) {
+    for tag in tags {
+        clauses.push(
+            "EXISTS (SELECT 1 FROM request_tags WHERE request_tags.request_id = requests.id AND request_tags.tag = ?)",
+        );
+        params.push(turso::Value::Text(tag.clone()));
+    }
+}
+
+/// `(?, ?, ?)` and the ids to go with it.
+fn id_list(ids: &[i64]) -> (String, Vec<turso::Value>) {
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let params = ids.iter().map(|id| turso::Value::Integer(*id)).collect();
+    (format!("({})", placeholders), params)
+}
+
+/// Top level words of a statement, lowercased, with strings, quoted names and comments left out.
+/// `None` when there's more than one statement.
+fn top_level_words(sql: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut depth = 0usize;
+    let mut ended = false;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let mut boundary = true;
+        match c {
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                for c in chars.by_ref() {
+                    if c == close {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => ended = true,
+            c if c.is_alphanumeric() || c == '_' => {
+                if ended {
+                    return None;
+                }
+                boundary = false;
+                if depth == 0 {
+                    word.extend(c.to_lowercase());
+                }
+            }
+            _ => (),
+        }
+        if boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    Some(words)
+}
+
+/// A single `SELECT`, `EXPLAIN`, or `WITH` whose main statement is a `SELECT`.
+fn is_read_only_sql(sql: &str) -> bool {
+    let Some(words) = top_level_words(sql) else {
+        return false;
+    };
+    match words.first().map(String::as_str) {
+        Some("select" | "explain") => true,
+        // the cte bodies are in parentheses, the first statement keyword outside them decides
+        Some("with") => words
+            .iter()
+            .find(|word| {
+                matches!(
+                    word.as_str(),
+                    "select" | "insert" | "update" | "delete" | "replace" | "values"
+                )
+            })
+            .is_some_and(|word| word == "select" || word == "values"),
+        _ => false,
+    }
+}
+
+/// Escapes LIKE wildcards so user input is matched literally, used with `ESCAPE '\'`.
+fn escape_like(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+impl EntryFilter {
+    /// Parses the TUI filter bar and `db delete` filters, e.g.
+    /// `method:get service:user status:4xx from:2025-01-01 to:2025-01-31 pinned:no tag:bug-42`.
+    pub fn parse(input: &str) -> Result<EntryFilter, String> {
+        let mut filter = EntryFilter::default();
+
+        for token in input.split_whitespace() {
+            let Some((key, value)) = token.split_once(':') else {
+                return Err(format!("expected key:value, got `{token}`"));
+            };
+            if value.is_empty() {
+                // still typing
+                continue;
+            }
+
+            match key {
+                "method" | "m" => filter.method = Some(value.to_string()),
+                "service" | "s" => filter.service = Some(value.to_string()),
+                "status" => {
+                    let range = match value.strip_suffix("xx") {
+                        Some(class) => class
+                            .parse::<u16>()
+                            .ok()
+                            .filter(|class| (1..=5).contains(class))
+                            .map(|class| (class * 100, class * 100 + 99)),
+                        None => value.parse::<u16>().ok().map(|status| (status, status)),
+                    };
+                    filter.status = Some(range.ok_or_else(|| format!("invalid status `{value}`"))?);
+                }
+                "from" | "to" => {
+                    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                        .map_err(|_| format!("invalid date `{value}`, use YYYY-MM-DD"))?;
+                    // `to` is inclusive for the user, the db filter wants an exclusive upper bound
+                    let date = if key == "to" {
+                        date.checked_add_days(Days::new(1)).unwrap_or(date)
+                    } else {
+                        date
+                    };
+                    let start_of_day = DateTime::from_naive_utc_and_offset(
+                        date.and_hms_opt(0, 0, 0).unwrap_or_default(),
+                        Utc,
+                    );
+                    if key == "from" {
+                        filter.from = Some(start_of_day);
+                    } else {
+                        filter.to = Some(start_of_day);
+                    }
+                }
+                "pinned" => {
+                    filter.pinned = Some(match value {
+                        "yes" | "true" => true,
+                        "no" | "false" => false,
+                        _ => return Err(format!("invalid pinned `{value}`, use yes or no")),
+                    })
+                }
+                "tag" => filter.tags.push(value.to_string()),
+                other => return Err(format!("unknown filter `{other}`")),
+            }
+        }
+
+        Ok(filter)
+    }
+
+    /// Nothing set, so it matches every request.
+    pub fn is_empty(&self) -> bool {
+        self.where_clause().0.is_empty()
+    }
+
+    fn where_clause(&self) -> (String, Vec<turso::Value>) {
+        let mut clauses: Vec<&str> = Vec::new();
+        let mut params: Vec<turso::Value> = Vec::new();
+
+        if let Some(method) = &self.method {
+            clauses.push("UPPER(method) = ?");
+            params.push(turso::Value::Text(method.to_uppercase()));
+        }
+        if let Some(service) = &self.service {
+            clauses.push("service LIKE ? ESCAPE '\\'");
+            params.push(turso::Value::Text(format!("%{}%", escape_like(service))));
+        }
+        if let Some((low, high)) = self.status {
+            clauses.push("status BETWEEN ? AND ?");
+            params.push(turso::Value::Integer(low as i64));
+            params.push(turso::Value::Integer(high as i64));
+        }
+        if let Some(id) = self.id {
+            clauses.push("id = ?");
+            params.push(turso::Value::Integer(id));
+        }
+        if let Some(pinned) = self.pinned {
+            clauses.push("pinned = ?");
+            params.push(turso::Value::Integer(pinned as i64));
+        }
+        push_tag_clauses(&self.tags, &mut clauses, &mut params);
+        if let Some(after_id) = self.after_id {
+            clauses.push("id > ?");
+            params.push(turso::Value::Integer(after_id));
+        }
+        if let Some(from) = self.from {
+            clauses.push("created_at >= ?");
+            params.push(turso::Value::Text(from.to_rfc3339()));
+        }
+        if let Some(to) = self.to {
+            clauses.push("created_at < ?");
+            params.push(turso::Value::Text(to.to_rfc3339()));
+        }
+        if let Some(search) = self
+            .search
+            .as_ref()
+            .filter(|search| !search.trim().is_empty())
+        {
+            // `%` between every character turns LIKE into a subsequence match
+            let pattern: String = search
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .map(|c| format!("%{}", escape_like(&c.to_string())))
+                .collect();
+            clauses.push(
+                "('#' || id || ' ' || method || ' ' || service || ' ' || route_url || ' ' || created_at || ' ' || COALESCE(note, '')) LIKE ? ESCAPE '\\'",
+            );
+            params.push(turso::Value::Text(format!("{}%", pattern)));
+        }
+
+        if clauses.is_empty() {
+            (String::new(), params)
+        } else {
+            (format!("WHERE {}", clauses.join(" AND ")), params)
+        }
+    }
+}
+
+impl ScoobyRequest {
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+
+    pub fn service(&self) -> &str {
+        &self.service
+    }
+
+    pub fn route_url(&self) -> &str {
+        &self.route_url
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn server_env(&self) -> Option<&str> {
+        self.server_env.as_deref()
+    }
+
+    pub fn pinned(&self) -> bool {
+        self.pinned
+    }
+
+    pub fn payload_json(&self) -> Option<&Value> {
+        self.payload_json.as_ref()
+    }
+
+    pub fn response_json(&self) -> Option<&Value> {
+        self.response_json.as_ref()
+    }
+}
+
+/// `user@host`, or whichever of them is known.
+pub fn made_by(user: Option<&str>, host: Option<&str>) -> Option<String> {
+    match (user, host) {
+        (Some(user), Some(host)) => Some(format!("{user}@{host}")),
+        (Some(user), None) => Some(user.to_string()),
+        (None, Some(host)) => Some(format!("@{host}")),
+        (None, None) => None,
+    }
 }
 
 fn colored_json_opt(v: &Option<Value>) -> String {
@@ -64,15 +636,42 @@ fn colored_json_opt(v: &Option<Value>) -> String {
 
 impl Display for ScoobyRequest {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let pin = if self.pinned {
+            format!("{} ", "★".yellow().bold())
+        } else {
+            String::new()
+        };
         writeln!(
             f,
-            "[{}] {} {} {}",
+            "{}#{} [{}] {} {} {}",
+            pin,
+            self.id,
             self.created_at,
             self.method.purple(),
             self.service.green(),
             self.url.yellow()
         )?;
 
+        if let Some(note) = &self.note {
+            writeln!(f, "  note: {}", note.italic())?;
+        }
+
+        if !self.tags.is_empty() {
+            writeln!(f, "  tags: {}", self.tags.join(", ").cyan())?;
+        }
+
+        if let Some(status) = self.status {
+            writeln!(f, "  status: {}", status)?;
+        }
+
+        if let Some(by) = made_by(self.user.as_deref(), self.host.as_deref()) {
+            writeln!(f, "  by: {}", by)?;
+        }
+
+        if self.attempts > 1 {
+            writeln!(f, "  attempts: {}", self.attempts)?;
+        }
+
         writeln!(f, "  payload: {}", colored_json_opt(&self.payload_json))?;
         writeln!(f, "  response: {}", colored_json_opt(&self.response_json))?;
 
@@ -80,6 +679,34 @@ impl Display for ScoobyRequest {
     }
 }
 
+/// Whatever a raw query from the sql console returned, every value already turned into text.
+pub struct SqlOutput {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    /// more rows were returned than were kept
+    pub truncated: bool,
+    /// rows changed, for statements that don't return anything
+    pub affected: Option<u64>,
+}
+
+fn display_value(value: turso::Value) -> String {
+    match value {
+        turso::Value::Null => "NULL".into(),
+        turso::Value::Integer(int) => int.to_string(),
+        turso::Value::Real(real) => real.to_string(),
+        turso::Value::Text(text) => text,
+        turso::Value::Blob(blob) => format!("<blob, {} bytes>", blob.len()),
+    }
+}
+
+/// How long opening the db waits for another process to let go of it.
+const LOCK_WAIT: Duration = Duration::from_secs(3);
+
+// turso doesn't have a dedicated error kind for this
+fn is_locked(err: &turso::Error) -> bool {
+    err.to_string().contains("locked by another process")
+}
+
 fn parse_json_opt(s: Option<String>) -> Result<Option<Value>, serde_json::Error> {
     match s {
         None => Ok(None),
@@ -90,9 +717,44 @@ fn parse_json_opt(s: Option<String>) -> Result<Option<Value>, serde_json::Error>
     }
 }
 
+/// Opens the file without touching the schema. The file is locked while another scooby (e.g.
+/// `db ui`) is using it, which only lasts for a moment so wait for it instead of bailing.
+async fn connect(full_path: &str) -> Result<Connection, Box<dyn Error>> {
+    let started = Instant::now();
+    let db = loop {
+        match Builder::new_local(full_path).build().await {
+            Ok(db) => break db,
+            Err(err) if is_locked(&err) && started.elapsed() < LOCK_WAIT => {
+                tokio::time::sleep(Duration::from_millis(20)).await
+            }
+            Err(err) => return Err(err.into()),
+        }
+    };
+    Ok(db
+        .connect()
+        .map_err(|err| format!("couldn't establish connection to db: {err}"))?)
+}
+
+/// A db that was opened once and closed again, reopening skips the migrations and the key check.
+pub struct DbHandle {
+    path: String,
+    cipher: Option<Cipher>,
+}
+
+impl DbHandle {
+    pub async fn reopen(&self) -> Result<Db, Box<dyn Error>> {
+        Ok(Db {
+            conn: connect(&self.path).await?,
+            path: self.path.clone(),
+            cipher: self.cipher.clone(),
+        })
+    }
+}
+
 impl Db {
     async fn setup_tables(&self) -> Result<(), Box<dyn Error>> {
-        const SQL_STR: &str = "CREATE TABLE IF NOT EXISTS requests (
+        const SQL_STRS: [&str; 9] = [
+            "CREATE TABLE IF NOT EXISTS requests (
         id INTEGER PRIMARY KEY AUTOINCREMENT,
         method TEXT NOT NULL,
         service TEXT NOT NULL,
@@ -100,75 +762,494 @@ impl Db {
         full_url TEXT NOT NULL,
         payload TEXT,
         response_json TEXT,
-        created_at TEXT NOT NULL)";
+        created_at TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS bench_runs (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        method TEXT NOT NULL,
+        service TEXT NOT NULL,
+        full_url TEXT NOT NULL,
+        requests INTEGER NOT NULL,
+        concurrency INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        throughput REAL NOT NULL,
+        p50_ms REAL NOT NULL,
+        p90_ms REAL NOT NULL,
+        p99_ms REAL NOT NULL,
+        statuses_json TEXT NOT NULL,
+        errors_json TEXT NOT NULL,
+        created_at TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS request_attempts (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        request_id INTEGER NOT NULL,
+        attempt INTEGER NOT NULL,
+        status INTEGER,
+        error TEXT,
+        elapsed_ms INTEGER NOT NULL,
+        delay_ms INTEGER NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS sql_history (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        query TEXT NOT NULL,
+        created_at TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS ui_settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS request_tags (
+        request_id INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (request_id, tag))",
+            "CREATE INDEX IF NOT EXISTS request_tags_tag ON request_tags (tag)",
+            "CREATE TABLE IF NOT EXISTS encryption (
+        name TEXT PRIMARY KEY,
+        value TEXT NOT NULL)",
+            "CREATE TABLE IF NOT EXISTS sync_state (
+        key TEXT PRIMARY KEY,
+        value INTEGER NOT NULL)",
+        ];
 
-        match self.conn.execute(SQL_STR, ()).await {
-            Ok(_) => Ok(()),
-            Err(err) => {
-                eprintln!("Couldn't setup tables: {}", err);
-                std::process::exit(1)
-            }
+        // columns added after the first release, existing databases get them through ALTER TABLE
+        const REQUESTS_COLUMNS: [(&str, &str); 12] = [
+            ("attempts", "INTEGER NOT NULL DEFAULT 1"),
+            ("status", "INTEGER"),
+            ("server_env", "TEXT"),
+            ("qsp", "TEXT"),
+            ("request_headers", "TEXT"),
+            ("response_headers", "TEXT"),
+            ("elapsed_ms", "INTEGER"),
+            ("pinned", "INTEGER NOT NULL DEFAULT 0"),
+            ("note", "TEXT"),
+            ("user", "TEXT"),
+            ("host", "TEXT"),
+            // filled in for older rows by `fill_content_hashes`
+            ("content_hash", "TEXT"),
+        ];
+
+        for sql_str in SQL_STRS {
+            self.conn
+                .execute(sql_str, ())
+                .await
+                .map_err(|err| format!("couldn't setup tables: {err}"))?;
         }
-    }
 
-    pub async fn create_connection() -> Result<Self, Box<dyn Error>> {
-        const LOCAL_DB_DIR_PATH: &str = ".scooby";
-        let local_db_path = format!("{}/{}", std::env::var("HOME")?, LOCAL_DB_DIR_PATH);
-        if !Path::new(&local_db_path).exists() {
-            fs::create_dir_all(local_db_path.clone())
+        for (column, decl) in REQUESTS_COLUMNS {
+            self.ensure_column("requests", column, decl)
                 .await
-                .unwrap_or_else(|err| {
-                    eprintln!(
-                        "Failed to create a scooby db directory at {}: {}",
-                        local_db_path, err
-                    );
-                    eprintln!("Exiting...");
-                    std::process::exit(1)
-                })
-        }
-
-        let full_path = format!("{}/{}", local_db_path, "dooby.db");
-        let db = match Builder::new_local(&full_path).build().await {
-            Ok(db) => db,
-            Err(err) => {
-                eprintln!("Local database connection failed with: {}", err);
-                eprintln!("Exiting...");
-                std::process::exit(1)
+                .map_err(|err| format!("couldn't migrate table requests: {err}"))?;
+        }
+
+        self.conn
+            .execute(
+                "CREATE UNIQUE INDEX IF NOT EXISTS requests_content_hash ON requests (content_hash)",
+                (),
+            )
+            .await
+            .map_err(|err| format!("couldn't migrate table requests: {err}"))?;
+
+        Ok(())
+    }
+
+    async fn ensure_column(
+        &self,
+        table: &str,
+        column: &str,
+        decl: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        let sql_str = format!("SELECT name FROM pragma_table_info('{}')", table);
+        let mut rows = self.conn.query(&sql_str, ()).await?;
+
+        while let Some(row) = rows.next().await? {
+            let name: String = row.get(0)?;
+            if name == column {
+                return Ok(());
             }
+        }
+
+        let sql_str = format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl);
+        self.conn.execute(&sql_str, ()).await?;
+
+        Ok(())
+    }
+
+    /// Opens the db at `full_path`, see `workspace::resolve_db_path`, creating it if needed.
+    /// Never exits, the caller decides how to report a broken db.
+    pub async fn create_connection(full_path: &str) -> Result<Self, Box<dyn Error>> {
+        if let Some(db_dir) = Path::new(full_path).parent()
+            && !db_dir.as_os_str().is_empty()
+            && !db_dir.exists()
+        {
+            fs::create_dir_all(db_dir).await.map_err(|err| {
+                format!(
+                    "failed to create a scooby db directory at {}: {}",
+                    db_dir.display(),
+                    err
+                )
+            })?;
+        }
+
+        let mut db = Self {
+            conn: connect(full_path).await?,
+            path: full_path.to_string(),
+            cipher: None,
+        };
+
+        db.setup_tables().await?;
+        db.cipher = db.load_cipher().await?;
+
+        Ok(db)
+    }
+
+    /// What `DbHandle::reopen` needs, the tables and the key are already sorted out by then.
+    pub fn handle(&self) -> DbHandle {
+        DbHandle {
+            path: self.path.clone(),
+            cipher: self.cipher.clone(),
+        }
+    }
+
+    async fn get_encryption_value(&self, name: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let mut rows = self
+            .conn
+            .query("SELECT value FROM encryption WHERE name = ?1", [name])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Needs the key when the db is already encrypted or encryption was just turned on. The
+    /// first time around a salt, the kdf parameters and a check value are stored, so a wrong key
+    /// is caught before anything gets written with it.
+    async fn load_cipher(&self) -> Result<Option<Cipher>, Box<dyn Error>> {
+        let salt = self.get_encryption_value("salt").await?;
+        let check = self.get_encryption_value("check").await?;
+        let (salt, check) = match (salt, check) {
+            (Some(salt), Some(check)) => (salt, Some(check)),
+            _ if !encryption::enabled() => return Ok(None),
+            _ => (encryption::generate_salt(), None),
+        };
+        let stored_kdf = self.get_encryption_value("kdf").await?;
+        let kdf = match &stored_kdf {
+            Some(kdf) => kdf
+                .parse()
+                .map_err(|err| format!("{}: {}", self.path, err))?,
+            None => Kdf::default(),
         };
 
-        let conn = match db.connect() {
-            Ok(conn) => conn,
-            Err(err) => {
-                eprintln!("Couldn't establish connection to db: {}", err);
-                std::process::exit(1)
+        let cipher = Cipher::for_salt(&salt, kdf)
+            .map_err(|err| format!("{} is encrypted: {}", self.path, err))?;
+        let mut values = Vec::new();
+        match check {
+            Some(check) => {
+                if cipher.decrypt(&check).ok().as_deref() != Some(CHECK_TEXT) {
+                    return Err(format!("wrong key for {}", self.path).into());
+                }
             }
+            None => {
+                values.push(("salt", salt));
+                values.push(("check", cipher.encrypt(CHECK_TEXT)?));
+            }
+        }
+        if stored_kdf.is_none() {
+            values.push(("kdf", kdf.to_string()));
+        }
+        for (name, value) in values {
+            self.conn
+                .execute(
+                    "INSERT INTO encryption (name, value) VALUES (?1, ?2)",
+                    (name, value),
+                )
+                .await?;
+        }
+        Ok(Some(cipher))
+    }
+
+    /// Salt, kdf and check of an encrypted db, `None` if it isn't encrypted.
+    pub async fn encryption_params(&self) -> Result<Option<EncryptionParams>, Box<dyn Error>> {
+        let salt = self.get_encryption_value("salt").await?;
+        let check = self.get_encryption_value("check").await?;
+        let kdf = self.get_encryption_value("kdf").await?;
+        Ok(match (salt, check) {
+            (Some(salt), Some(check)) => Some(EncryptionParams {
+                salt,
+                kdf: kdf.unwrap_or_else(|| Kdf::default().to_string()),
+                check,
+            }),
+            _ => None,
+        })
+    }
+
+    /// Makes sure values encrypted in a db with `params` can be decrypted here. Fine as is when
+    /// the key here opens their check value. Otherwise a db with nothing encrypted in it yet
+    /// takes over their salt and kdf, so the same passphrase gives the same key. A db that
+    /// already has values encrypted with its own salt can't, it'd lose those.
+    pub async fn adopt_encryption(
+        &mut self,
+        params: &EncryptionParams,
+    ) -> Result<(), Box<dyn Error>> {
+        if let Some(cipher) = &self.cipher
+            && cipher.decrypt(&params.check).ok().as_deref() == Some(CHECK_TEXT)
+        {
+            return Ok(());
+        }
+
+        let prefix = format!("{}%", encryption::PREFIX);
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT COUNT(*) FROM requests WHERE payload LIKE ?1 OR response_json LIKE ?1
+                OR request_headers LIKE ?1 OR response_headers LIKE ?1",
+                [prefix.as_str()],
+            )
+            .await?;
+        let encrypted: i64 = match rows.next().await? {
+            Some(row) => row.get(0)?,
+            None => 0,
         };
+        if encrypted > 0 {
+            return Err(format!(
+                "the export was encrypted with another salt than the {encrypted} encrypted requests in {}, import into a new database or use a shared SCOOBY_DB_KEY",
+                self.path
+            )
+            .into());
+        }
 
-        let db = Self { conn };
+        let kdf: Kdf = params.kdf.parse()?;
+        let cipher = Cipher::for_salt(&params.salt, kdf)
+            .map_err(|err| format!("the export is encrypted: {err}"))?;
+        if cipher.decrypt(&params.check).ok().as_deref() != Some(CHECK_TEXT) {
+            return Err("wrong key for the export".into());
+        }
 
-        let _ = db.setup_tables().await;
+        self.conn.execute("BEGIN", ()).await?;
+        let result = async {
+            self.conn.execute("DELETE FROM encryption", ()).await?;
+            for (name, value) in [
+                ("salt", &params.salt),
+                ("kdf", &params.kdf),
+                ("check", &params.check),
+            ] {
+                self.conn
+                    .execute(
+                        "INSERT INTO encryption (name, value) VALUES (?1, ?2)",
+                        (name, value.as_str()),
+                    )
+                    .await?;
+            }
+            Ok::<_, Box<dyn Error>>(())
+        }
+        .await;
+        if let Err(err) = result {
+            let _ = self.conn.execute("ROLLBACK", ()).await;
+            return Err(err);
+        }
+        self.conn.execute("COMMIT", ()).await?;
 
-        Ok(db)
+        self.cipher = Some(cipher);
+        Ok(())
+    }
+
+    /// Exact duplicates `fill_content_hashes` leaves without a hash, they don't sync or export.
+    pub async fn count_unhashed(&self) -> Result<i64, Box<dyn Error>> {
+        self.count("SELECT COUNT(*) FROM requests WHERE content_hash IS NULL")
+            .await
+    }
+
+    /// Encrypts a column value if there's a key.
+    fn seal(&self, value: String) -> Result<String, Box<dyn Error>> {
+        match &self.cipher {
+            Some(cipher) => Ok(cipher.encrypt(&value)?),
+            None => Ok(value),
+        }
+    }
+
+    /// Decrypts a column value if it was encrypted, plain values are passed through.
+    fn unseal(&self, value: Option<String>) -> Result<Option<String>, Box<dyn Error>> {
+        match (value, &self.cipher) {
+            (Some(value), Some(cipher)) if is_encrypted(&value) => {
+                Ok(Some(cipher.decrypt(&value)?))
+            }
+            (Some(value), None) if is_encrypted(&value) => {
+                Err("request is encrypted and there's no key".into())
+            }
+            (value, _) => Ok(value),
+        }
+    }
+
+    /// Decrypts and parses a json column. Rows synced or imported from a db with another key
+    /// can't be decrypted here, those read as `"<encrypted>"` instead of failing every listing.
+    fn unseal_json(&self, value: Option<String>) -> Result<Option<Value>, Box<dyn Error>> {
+        match value {
+            Some(value) if is_encrypted(&value) => {
+                let plain = self.cipher.as_ref().map(|cipher| cipher.decrypt(&value));
+                match plain {
+                    Some(Ok(plain)) => Ok(Some(from_str(&plain)?)),
+                    _ => Ok(Some(Value::from(UNREADABLE))),
+                }
+            }
+            value => Ok(parse_json_opt(value)?),
+        }
     }
 
     pub async fn insert_args(
         &self,
         store_args: DbStoreArgs,
         res: ResponseParts,
+    ) -> Result<i64, Box<dyn Error>> {
+        let elapsed = res.elapsed;
+        self.insert_request(store_args, Some(res), elapsed).await
+    }
+
+    /// Stores a request whose every attempt errored. There's no response, the errors are in its
+    /// attempts.
+    pub async fn insert_failed(
+        &self,
+        store_args: DbStoreArgs,
+        elapsed: Duration,
+    ) -> Result<i64, Box<dyn Error>> {
+        self.insert_request(store_args, None, elapsed).await
+    }
+
+    async fn insert_request(
+        &self,
+        store_args: DbStoreArgs,
+        res: Option<ResponseParts>,
+        elapsed: Duration,
+    ) -> Result<i64, Box<dyn Error>> {
+        // nothing secret should ever hit the disk
+        let redactor = redactor();
+        let mut payload = store_args.payload;
+        if let Some(payload) = &mut payload {
+            redactor.json(payload);
+        }
+        let (status, response_json_string, response_headers) = match res {
+            Some(res) => {
+                let mut body = res.body;
+                redactor.json(&mut body);
+                (
+                    Some(res.status.as_u16() as i64),
+                    Some(serde_json::to_string(&body)?),
+                    Some(masked_headers_to_json(&res.headers).to_string()),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        let payload_json_string = serde_json::to_string(&payload)?;
+
+        let now: DateTime<Utc> = SystemTime::now().into();
+        let created_at = now.to_rfc3339();
+        let full_url = redactor.text(&store_args.url);
+        let (user, host) = origin();
+        // hashed before encrypting, the ciphertext is different every time
+        let hash = content_hash(
+            &store_args.method,
+            &store_args.service,
+            &full_url,
+            Some(&payload_json_string),
+            response_json_string.as_deref(),
+            &created_at,
+            status,
+            user.as_deref(),
+            host.as_deref(),
+        );
+
+        let stored = StoredRequest {
+            content_hash: hash,
+            method: store_args.method,
+            service: store_args.service,
+            route_url: redactor.text(&store_args.route_url),
+            full_url,
+            payload: Some(self.seal(payload_json_string)?),
+            response_json: response_json_string
+                .map(|value| self.seal(value))
+                .transpose()?,
+            created_at,
+            attempts: store_args.attempts.len().max(1) as i64,
+            status,
+            server_env: store_args.server_env,
+            qsp: store_args
+                .qsp
+                .filter(|qsp| !qsp.is_empty())
+                .map(|qsp| redactor.text(&qsp)),
+            request_headers: Some(
+                self.seal(masked_headers_to_json(&store_args.request_headers).to_string())?,
+            ),
+            response_headers: response_headers.map(|value| self.seal(value)).transpose()?,
+            elapsed_ms: Some(elapsed.as_millis() as i64),
+            pinned: false,
+            note: None,
+            user,
+            host,
+        };
+
+        self.conn
+            .execute(&insert_stored_sql(), stored.params())
+            .await?;
+
+        let request_id = self.conn.last_insert_rowid();
+        self.insert_attempts(request_id, &store_args.attempts)
+            .await?;
+        for tag in &store_args.tags {
+            self.insert_tag(request_id, tag).await?;
+        }
+
+        Ok(request_id)
+    }
+
+    async fn insert_attempts(
+        &self,
+        request_id: i64,
+        attempts: &[Attempt],
     ) -> Result<(), Box<dyn Error>> {
-        const SQL_STR: &str = "INSERT INTO requests (
+        const SQL_STR: &str = "INSERT INTO request_attempts (
+        request_id,
+        attempt,
+        status,
+        error,
+        elapsed_ms,
+        delay_ms
+    ) VALUES (?, ?, ?, ?, ?, ?)";
+
+        for attempt in attempts {
+            self.conn
+                .execute(
+                    SQL_STR,
+                    (
+                        request_id,
+                        attempt.number as i64,
+                        attempt.status.map(|status| status as i64),
+                        attempt.error.as_deref().map(|error| redactor().text(error)),
+                        attempt.elapsed_ms as i64,
+                        attempt.delay_ms as i64,
+                    ),
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn insert_bench_summary(&self, summary: &BenchSummary) -> Result<(), Box<dyn Error>> {
+        const SQL_STR: &str = "INSERT INTO bench_runs (
         method,
         service,
-        route_url,
         full_url,
-        payload,
-        response_json,
+        requests,
+        concurrency,
+        duration_ms,
+        throughput,
+        p50_ms,
+        p90_ms,
+        p99_ms,
+        statuses_json,
+        errors_json,
         created_at
-    ) VALUES (?, ?, ?, ?, ?, ?, ?)";
+    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
 
-        let payload_json_string = serde_json::to_string(&store_args.payload)?;
-        let response_json_string = serde_json::to_string(&res.body)?;
+        // reqwest errors carry the full url, querystring tokens included
+        let redactor = redactor();
+        let statuses_json_string = serde_json::to_string(&summary.statuses)?;
+        let errors_json_string = redactor.text(&serde_json::to_string(&summary.errors)?);
 
         let now: DateTime<Utc> = SystemTime::now().into();
         let created_at = now.to_rfc3339();
@@ -177,117 +1258,862 @@ impl Db {
             .execute(
                 SQL_STR,
                 (
-                    store_args.method,
-                    store_args.service,
-                    store_args.route_url,
-                    store_args.url,
-                    payload_json_string,
-                    response_json_string,
+                    summary.method.clone(),
+                    summary.service.clone(),
+                    redactor.text(&summary.url),
+                    summary.requests as i64,
+                    summary.concurrency as i64,
+                    summary.duration.as_millis() as i64,
+                    summary.throughput,
+                    summary.p50_ms,
+                    summary.p90_ms,
+                    summary.p99_ms,
+                    statuses_json_string,
+                    errors_json_string,
                     created_at,
                 ),
             )
-            .await
-            .unwrap_or_else(|err| {
-                eprintln!("{}: {}", "Ruh roh, couldn't store valus in db!".red(), err);
-                eprintln!("Exiting...");
-                std::process::exit(1)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_entries(&self, filter: &EntryFilter) -> Result<i64, Box<dyn Error>> {
+        let (where_clause, params) = filter.where_clause();
+        let sql_str = format!("SELECT COUNT(*) FROM requests {}", where_clause);
+
+        let mut rows = self.conn.query(&sql_str, params).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Runs a single statement as typed into the sql console. Unless `allow_writes` is set the
+    /// connection is put in query_only mode, so anything that would change the db fails.
+    pub async fn run_sql(
+        &self,
+        sql: &str,
+        allow_writes: bool,
+        max_rows: usize,
+    ) -> Result<SqlOutput, Box<dyn Error>> {
+        // query_only alone still lets writing pragmas, ATTACH and VACUUM through
+        if !allow_writes && !is_read_only_sql(sql) {
+            return Err(
+                "only SELECT, EXPLAIN and WITH ... SELECT run without writes enabled".into(),
+            );
+        }
+
+        let query_only = if allow_writes { 0 } else { 1 };
+        self.conn
+            .execute(&format!("PRAGMA query_only = {}", query_only), ())
+            .await?;
+        let output = self.run_statement(sql, max_rows).await;
+        self.conn.execute("PRAGMA query_only = 0", ()).await?;
+        output
+    }
+
+    async fn run_statement(&self, sql: &str, max_rows: usize) -> Result<SqlOutput, Box<dyn Error>> {
+        let mut stmt = self.conn.prepare(sql).await?;
+        let columns: Vec<String> = stmt
+            .columns()
+            .iter()
+            .map(|column| column.name().to_string())
+            .collect();
+
+        if columns.is_empty() {
+            let affected = stmt.execute(()).await?;
+            return Ok(SqlOutput {
+                columns,
+                rows: Vec::new(),
+                truncated: false,
+                affected: Some(affected),
             });
+        }
+
+        let mut rows = stmt.query(()).await?;
+        let mut output = Vec::new();
+        let mut truncated = false;
+        while let Some(row) = rows.next().await? {
+            if output.len() == max_rows {
+                truncated = true;
+                break;
+            }
+            let values = (0..row.column_count())
+                .map(|idx| row.get_value(idx).map(display_value))
+                .collect::<Result<Vec<_>, _>>()?;
+            output.push(values);
+        }
+
+        Ok(SqlOutput {
+            columns,
+            rows: output,
+            truncated,
+            affected: None,
+        })
+    }
+
+    pub async fn add_sql_history(&self, query: &str) -> Result<(), Box<dyn Error>> {
+        let now: DateTime<Utc> = SystemTime::now().into();
+        self.conn
+            .execute(
+                "INSERT INTO sql_history (query, created_at) VALUES (?1, ?2)",
+                (query, now.to_rfc3339()),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Most recent `limit` queries, oldest first.
+    pub async fn get_sql_history(&self, limit: i64) -> Result<Vec<String>, Box<dyn Error>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT query FROM sql_history ORDER BY id DESC LIMIT ?1",
+                [limit],
+            )
+            .await?;
+        let mut history = Vec::new();
+        while let Some(row) = rows.next().await? {
+            history.push(row.get::<String>(0)?);
+        }
+        history.reverse();
+        Ok(history)
+    }
 
+    /// Things the TUI remembers between sessions, like the pane sizes.
+    pub async fn get_ui_setting(&self, key: &str) -> Result<Option<String>, Box<dyn Error>> {
+        let mut rows = self
+            .conn
+            .query("SELECT value FROM ui_settings WHERE key = ?1", [key])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(Some(row.get(0)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set_ui_setting(&self, key: &str, value: &str) -> Result<(), Box<dyn Error>> {
+        self.conn
+            .execute(
+                "INSERT INTO ui_settings (key, value) VALUES (?1, ?2)
+                ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                (key, value),
+            )
+            .await?;
         Ok(())
     }
 
-    pub async fn get_all_entries(&self) -> Result<Vec<ScoobyRequest>, Box<dyn Error>> {
-        const SQL_STR: &str = "SELECT * FROM requests ORDER BY created_at DESC";
+    /// Id of the newest stored request, `None` when nothing is stored yet.
+    pub async fn latest_id(&self) -> Result<Option<i64>, Box<dyn Error>> {
+        let mut rows = self.conn.query("SELECT MAX(id) FROM requests", ()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Newest first, only the summary columns so paging through a big history stays cheap.
+    pub async fn get_summaries_page(
+        &self,
+        filter: &EntryFilter,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RequestSummary>, Box<dyn Error>> {
+        let (where_clause, mut params) = filter.where_clause();
+        let sql_str = format!(
+            "SELECT id, method, service, route_url, status, created_at, pinned, note FROM requests {}
+        ORDER BY created_at DESC, id DESC LIMIT ? OFFSET ?",
+            where_clause
+        );
+        params.push(turso::Value::Integer(limit));
+        params.push(turso::Value::Integer(offset));
 
-        let mut rows = self.conn.query(SQL_STR, [1]).await?;
+        let mut rows = self.conn.query(&sql_str, params).await?;
         let mut output = Vec::new();
 
         while let Some(row) = rows.next().await? {
-            let data = match Db::map_to_domain(row).await {
-                Ok(mapped) => mapped,
+            let status: Option<i64> = row.get(4)?;
+            let created_at_text: String = row.get(5)?;
+
+            output.push(RequestSummary {
+                id: row.get(0)?,
+                method: row.get(1)?,
+                service: row.get(2)?,
+                route_url: row.get(3)?,
+                status: status.and_then(|status| u16::try_from(status).ok()),
+                created_at: created_at_text.parse::<DateTime<Utc>>()?,
+                pinned: row.get::<i64>(6)? != 0,
+                note: row.get(7)?,
+                tags: Vec::new(),
+            });
+        }
+
+        let ids: Vec<i64> = output.iter().map(|summary| summary.id).collect();
+        let mut tags = self.get_tags(&ids).await?;
+        for summary in &mut output {
+            summary.tags = tags.remove(&summary.id).unwrap_or_default();
+        }
+
+        Ok(output)
+    }
+
+    /// Returns false when there's no request with that id.
+    pub async fn set_pinned(&self, id: i64, pinned: bool) -> Result<bool, Box<dyn Error>> {
+        let changed = self
+            .conn
+            .execute(
+                "UPDATE requests SET pinned = ?1 WHERE id = ?2",
+                (pinned as i64, id),
+            )
+            .await?;
+        Ok(changed > 0)
+    }
+
+    /// `None` removes the note. Returns false when there's no request with that id.
+    pub async fn set_note(&self, id: i64, note: Option<&str>) -> Result<bool, Box<dyn Error>> {
+        let changed = self
+            .conn
+            .execute("UPDATE requests SET note = ?1 WHERE id = ?2", (note, id))
+            .await?;
+        Ok(changed > 0)
+    }
+
+    async fn insert_tag(&self, request_id: i64, tag: &str) -> Result<(), Box<dyn Error>> {
+        self.conn
+            .execute(
+                "INSERT INTO request_tags (request_id, tag) VALUES (?1, ?2) ON CONFLICT DO NOTHING",
+                (request_id, tag),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Tagging twice is fine. Returns false when there's no request with that id.
+    pub async fn add_tag(&self, id: i64, tag: &str) -> Result<bool, Box<dyn Error>> {
+        let mut rows = self
+            .conn
+            .query("SELECT 1 FROM requests WHERE id = ?1", [id])
+            .await?;
+        if rows.next().await?.is_none() {
+            return Ok(false);
+        }
+        self.insert_tag(id, tag).await?;
+        Ok(true)
+    }
+
+    /// Returns false when the request didn't have that tag.
+    pub async fn remove_tag(&self, id: i64, tag: &str) -> Result<bool, Box<dyn Error>> {
+        let removed = self
+            .conn
+            .execute(
+                "DELETE FROM request_tags WHERE request_id = ?1 AND tag = ?2",
+                (id, tag),
+            )
+            .await?;
+        Ok(removed > 0)
+    }
+
+    /// Tags of the given requests, sorted. Requests without tags are left out.
+    pub async fn get_tags(&self, ids: &[i64]) -> Result<HashMap<i64, Vec<String>>, Box<dyn Error>> {
+        let mut tags: HashMap<i64, Vec<String>> = HashMap::new();
+        for chunk in ids.chunks(ID_CHUNK_SIZE) {
+            let (list, params) = id_list(chunk);
+            let sql_str = format!(
+                "SELECT request_id, tag FROM request_tags WHERE request_id IN {} ORDER BY tag",
+                list
+            );
+            let mut rows = self.conn.query(&sql_str, params).await?;
+            while let Some(row) = rows.next().await? {
+                tags.entry(row.get(0)?).or_default().push(row.get(1)?);
+            }
+        }
+        Ok(tags)
+    }
+
+    /// Every tag along with the content hash of its request, for `db sync`.
+    pub async fn get_hashed_tags(&self) -> Result<Vec<(String, String)>, Box<dyn Error>> {
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT requests.content_hash, request_tags.tag FROM request_tags
+                JOIN requests ON requests.id = request_tags.request_id
+                WHERE requests.content_hash IS NOT NULL
+                ORDER BY request_tags.request_id, request_tags.tag",
+                (),
+            )
+            .await?;
+        let mut tags = Vec::new();
+        while let Some(row) = rows.next().await? {
+            tags.push((row.get(0)?, row.get(1)?));
+        }
+        Ok(tags)
+    }
+
+    /// Tags the request with that content hash, false when it's not here or already tagged.
+    pub async fn add_tag_by_hash(
+        &self,
+        content_hash: &str,
+        tag: &str,
+    ) -> Result<bool, Box<dyn Error>> {
+        let added = self
+            .conn
+            .execute(
+                "INSERT INTO request_tags (request_id, tag)
+                SELECT id, ?2 FROM requests WHERE content_hash = ?1
+                ON CONFLICT DO NOTHING",
+                (content_hash, tag),
+            )
+            .await?;
+        Ok(added > 0)
+    }
+
+    async fn with_tags(
+        &self,
+        mut entries: Vec<ScoobyRequest>,
+    ) -> Result<Vec<ScoobyRequest>, Box<dyn Error>> {
+        let ids: Vec<i64> = entries.iter().map(|entry| entry.id).collect();
+        let mut tags = self.get_tags(&ids).await?;
+        for entry in &mut entries {
+            entry.tags = tags.remove(&entry.id).unwrap_or_default();
+        }
+        Ok(entries)
+    }
+
+    /// Ids of every request matching the filter, newest first.
+    pub async fn get_ids(&self, filter: &EntryFilter) -> Result<Vec<i64>, Box<dyn Error>> {
+        let (where_clause, params) = filter.where_clause();
+        let sql_str = format!(
+            "SELECT id FROM requests {} ORDER BY created_at DESC, id DESC",
+            where_clause
+        );
+
+        let mut rows = self.conn.query(&sql_str, params).await?;
+        let mut ids = Vec::new();
+        while let Some(row) = rows.next().await? {
+            ids.push(row.get(0)?);
+        }
+        Ok(ids)
+    }
+
+    /// Removes requests along with everything stored about them. Returns how many were deleted.
+    pub async fn delete_entries(&self, ids: &[i64]) -> Result<u64, Box<dyn Error>> {
+        let mut deleted = 0;
+        self.conn.execute("BEGIN", ()).await?;
+        for chunk in ids.chunks(ID_CHUNK_SIZE) {
+            let (list, params) = id_list(chunk);
+            let result = async {
+                for table in ["request_attempts", "request_tags"] {
+                    self.conn
+                        .execute(
+                            &format!("DELETE FROM {} WHERE request_id IN {}", table, list),
+                            params.clone(),
+                        )
+                        .await?;
+                }
+                self.conn
+                    .execute(
+                        &format!("DELETE FROM requests WHERE id IN {}", list),
+                        params,
+                    )
+                    .await
+            }
+            .await;
+            match result {
+                Ok(count) => deleted += count,
                 Err(err) => {
-                    eprintln!("Something went wrong mapping data to domain: {}", err);
-                    eprintln!("Exiting...");
-                    std::process::exit(1)
+                    let _ = self.conn.execute("ROLLBACK", ()).await;
+                    return Err(err.into());
                 }
+            }
+        }
+        self.conn.execute("COMMIT", ()).await?;
+        Ok(deleted)
+    }
+
+    /// Requests past any of the policy's limits, oldest ones go first.
+    pub async fn retention_ids(
+        &self,
+        policy: &RetentionPolicy,
+    ) -> Result<Vec<i64>, Box<dyn Error>> {
+        let pinned = policy.keep_pinned.then_some(false);
+        let mut ids = Vec::new();
+
+        if let Some(max_age) = policy.max_age {
+            let cutoff = Utc::now()
+                .checked_sub_signed(max_age)
+                .ok_or("max age reaches further back than dates go, use a smaller one")?;
+            let filter = EntryFilter {
+                to: Some(cutoff),
+                pinned,
+                ..EntryFilter::default()
             };
-            output.push(data);
+            ids.extend(self.get_ids(&filter).await?);
         }
 
-        Ok(output)
+        if policy.max_rows.is_some() || policy.max_body_bytes.is_some() {
+            let (where_clause, params) = EntryFilter {
+                pinned,
+                ..EntryFilter::default()
+            }
+            .where_clause();
+            let sql_str = format!(
+                "SELECT id, {} FROM requests {} ORDER BY created_at DESC, id DESC",
+                BODY_BYTES_SQL, where_clause
+            );
+            let mut rows = self.conn.query(&sql_str, params).await?;
+
+            // newest first, everything after the first one over a limit goes
+            let mut kept: u64 = 0;
+            let mut body_bytes: u64 = 0;
+            while let Some(row) = rows.next().await? {
+                let id: i64 = row.get(0)?;
+                let bytes: i64 = row.get(1)?;
+                kept += 1;
+                body_bytes += bytes.max(0) as u64;
+                let too_many = policy.max_rows.is_some_and(|max_rows| kept > max_rows);
+                let too_big = policy
+                    .max_body_bytes
+                    .is_some_and(|max_body_bytes| body_bytes > max_body_bytes);
+                if too_many || too_big {
+                    ids.push(id);
+                }
+            }
+        }
+
+        ids.sort_unstable();
+        ids.dedup();
+        Ok(ids)
+    }
+
+    /// Prunes whatever is past the policy's limits, returns how many requests went.
+    pub async fn apply_retention(&self, policy: &RetentionPolicy) -> Result<u64, Box<dyn Error>> {
+        if policy.is_empty() {
+            return Ok(0);
+        }
+        let ids = self.retention_ids(policy).await?;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        self.delete_entries(&ids).await
+    }
+
+    async fn count(&self, sql_str: &str) -> Result<i64, Box<dyn Error>> {
+        let mut rows = self.conn.query(sql_str, ()).await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(0),
+        }
+    }
+
+    /// `(id, created_at)` of the first request in `order`.
+    async fn edge_entry(
+        &self,
+        order: &str,
+    ) -> Result<Option<(i64, DateTime<Utc>)>, Box<dyn Error>> {
+        let sql_str = format!(
+            "SELECT id, created_at FROM requests ORDER BY created_at {0}, id {0} LIMIT 1",
+            order
+        );
+        let mut rows = self.conn.query(&sql_str, ()).await?;
+        match rows.next().await? {
+            Some(row) => {
+                let created_at: String = row.get(1)?;
+                Ok(Some((row.get(0)?, created_at.parse::<DateTime<Utc>>()?)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub async fn stats(&self) -> Result<DbStats, Box<dyn Error>> {
+        let mut file_bytes = None;
+        for path in [self.path.clone(), format!("{}-wal", self.path)] {
+            if let Ok(metadata) = fs::metadata(&path).await {
+                file_bytes = Some(file_bytes.unwrap_or(0) + metadata.len());
+            }
+        }
+
+        let sql_str = format!(
+            "SELECT service, COUNT(*), SUM({}), MIN(created_at) FROM requests GROUP BY service ORDER BY 3 DESC",
+            BODY_BYTES_SQL
+        );
+        let mut rows = self.conn.query(&sql_str, ()).await?;
+        let mut services = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let oldest: String = row.get(3)?;
+            services.push(ServiceStats {
+                service: row.get(0)?,
+                requests: row.get(1)?,
+                body_bytes: row.get::<Option<i64>>(2)?.unwrap_or(0),
+                oldest: oldest.parse::<DateTime<Utc>>()?,
+            });
+        }
+
+        Ok(DbStats {
+            path: self.path.clone(),
+            file_bytes,
+            requests: self.count("SELECT COUNT(*) FROM requests").await?,
+            pinned: self
+                .count("SELECT COUNT(*) FROM requests WHERE pinned = 1")
+                .await?,
+            tagged: self
+                .count("SELECT COUNT(DISTINCT request_id) FROM request_tags")
+                .await?,
+            attempts: self.count("SELECT COUNT(*) FROM request_attempts").await?,
+            bench_runs: self.count("SELECT COUNT(*) FROM bench_runs").await?,
+            oldest: self.edge_entry("ASC").await?,
+            newest: self.edge_entry("DESC").await?,
+            services,
+        })
+    }
+
+    /// Encrypts bodies and headers stored before encryption was turned on. Returns how many
+    /// requests were changed.
+    pub async fn encrypt_existing(&self) -> Result<u64, Box<dyn Error>> {
+        if self.cipher.is_none() {
+            return Err("encryption isn't turned on, set enabled = true in [encryption]".into());
+        }
+
+        const SQL_STR: &str =
+            "SELECT id, payload, response_json, request_headers, response_headers FROM requests";
+        let mut rows = self.conn.query(SQL_STR, ()).await?;
+        let mut plain = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            let columns: [Option<String>; 4] = [row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?];
+            if columns.iter().flatten().any(|value| !is_encrypted(value)) {
+                plain.push((id, columns));
+            }
+        }
+
+        self.conn.execute("BEGIN", ()).await?;
+        for (id, columns) in &plain {
+            let result = async {
+                let mut sealed = Vec::new();
+                for value in columns {
+                    sealed.push(match value {
+                        Some(value) if !is_encrypted(value) => Some(self.seal(value.clone())?),
+                        value => value.clone(),
+                    });
+                }
+                self.conn
+                    .execute(
+                        "UPDATE requests SET payload = ?1, response_json = ?2, request_headers = ?3,
+                        response_headers = ?4 WHERE id = ?5",
+                        (
+                            sealed[0].clone(),
+                            sealed[1].clone(),
+                            sealed[2].clone(),
+                            sealed[3].clone(),
+                            *id,
+                        ),
+                    )
+                    .await?;
+                Ok::<_, Box<dyn Error>>(())
+            }
+            .await;
+            if let Err(err) = result {
+                let _ = self.conn.execute("ROLLBACK", ()).await;
+                return Err(err);
+            }
+        }
+        self.conn.execute("COMMIT", ()).await?;
+        Ok(plain.len() as u64)
+    }
+
+    /// Hashes requests stored before `content_hash` existed, they can't be synced without one.
+    pub async fn fill_content_hashes(&self) -> Result<u64, Box<dyn Error>> {
+        const SQL_STR: &str = "SELECT id, method, service, full_url, payload, response_json,
+        created_at, status, user, host FROM requests WHERE content_hash IS NULL";
+
+        let mut taken = HashSet::new();
+        let mut rows = self
+            .conn
+            .query(
+                "SELECT content_hash FROM requests WHERE content_hash IS NOT NULL",
+                (),
+            )
+            .await?;
+        while let Some(row) = rows.next().await? {
+            taken.insert(row.get::<String>(0)?);
+        }
+
+        let mut rows = self.conn.query(SQL_STR, ()).await?;
+        let mut hashes = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            let method: String = row.get(1)?;
+            let service: String = row.get(2)?;
+            let full_url: String = row.get(3)?;
+            let payload = self.unseal(row.get(4)?)?;
+            let response_json = self.unseal(row.get(5)?)?;
+            let created_at: String = row.get(6)?;
+            let status: Option<i64> = row.get(7)?;
+            let user: Option<String> = row.get(8)?;
+            let host: Option<String> = row.get(9)?;
+            let hash = content_hash(
+                &method,
+                &service,
+                &full_url,
+                payload.as_deref(),
+                response_json.as_deref(),
+                &created_at,
+                status,
+                user.as_deref(),
+                host.as_deref(),
+            );
+            // identical rows stored twice keep their NULL, the first one stands for both
+            if taken.insert(hash.clone()) {
+                hashes.push((id, hash));
+            }
+        }
+
+        for (id, hash) in &hashes {
+            self.conn
+                .execute(
+                    "UPDATE requests SET content_hash = ?1 WHERE id = ?2",
+                    (hash.as_str(), *id),
+                )
+                .await?;
+        }
+        Ok(hashes.len() as u64)
+    }
+
+    /// Rows with an id above `after_id`, oldest first, along with their ids.
+    pub async fn get_stored_since(
+        &self,
+        after_id: i64,
+        limit: i64,
+    ) -> Result<Vec<(i64, StoredRequest)>, Box<dyn Error>> {
+        let sql_str = format!(
+            "SELECT id, {} FROM requests WHERE id > ?1 AND content_hash IS NOT NULL
+            ORDER BY id ASC LIMIT ?2",
+            STORED_COLUMNS
+        );
+        let mut rows = self.conn.query(&sql_str, (after_id, limit)).await?;
+        let mut stored = Vec::new();
+        while let Some(row) = rows.next().await? {
+            let id: i64 = row.get(0)?;
+            let values = (1..row.column_count())
+                .map(|index| row.get_value(index))
+                .collect::<Result<_, _>>()?;
+            stored.push((id, StoredRequest::from_values(values)?));
+        }
+        Ok(stored)
+    }
+
+    /// Stores a row from another db, returns its new id or `None` if it's already here.
+    pub async fn insert_stored(
+        &self,
+        stored: &StoredRequest,
+    ) -> Result<Option<i64>, Box<dyn Error>> {
+        let sql_str = format!(
+            "{} ON CONFLICT (content_hash) DO NOTHING",
+            insert_stored_sql()
+        );
+        let inserted = self.conn.execute(&sql_str, stored.params()).await?;
+        Ok((inserted > 0).then(|| self.conn.last_insert_rowid()))
+    }
+
+    /// Stores rows from an export in one go, along with their tags. Rows that are already here
+    /// only get the tags they're missing. Returns how many rows were new.
+    pub async fn import_stored(
+        &self,
+        requests: &[(StoredRequest, Vec<String>)],
+    ) -> Result<u64, Box<dyn Error>> {
+        self.conn.execute("BEGIN", ()).await?;
+        let mut imported = 0;
+        for (stored, tags) in requests {
+            let result = async {
+                let id = if let Some(id) = self.insert_stored(stored).await? {
+                    imported += 1;
+                    id
+                } else {
+                    let mut rows = self
+                        .conn
+                        .query(
+                            "SELECT id FROM requests WHERE content_hash = ?1",
+                            [stored.content_hash.as_str()],
+                        )
+                        .await?;
+                    match rows.next().await? {
+                        Some(row) => row.get(0)?,
+                        None => return Err("request vanished while importing".into()),
+                    }
+                };
+                for tag in tags {
+                    self.insert_tag(id, tag).await?;
+                }
+                Ok::<_, Box<dyn Error>>(())
+            }
+            .await;
+            if let Err(err) = result {
+                let _ = self.conn.execute("ROLLBACK", ()).await;
+                return Err(err);
+            }
+        }
+        self.conn.execute("COMMIT", ()).await?;
+        Ok(imported)
+    }
+
+    /// How far `db sync` got with a remote, by a key like `<url> pushed`.
+    pub async fn get_sync_state(&self, key: &str) -> Result<i64, Box<dyn Error>> {
+        let mut rows = self
+            .conn
+            .query("SELECT value FROM sync_state WHERE key = ?1", [key])
+            .await?;
+        match rows.next().await? {
+            Some(row) => Ok(row.get(0)?),
+            None => Ok(0),
+        }
+    }
+
+    pub async fn set_sync_state(&self, key: &str, value: i64) -> Result<(), Box<dyn Error>> {
+        self.conn
+            .execute(
+                "INSERT INTO sync_state (key, value) VALUES (?1, ?2)
+                ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+                (key, value),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Payload and response of a single request, `None` if the id doesn't exist.
+    pub async fn get_bodies(
+        &self,
+        id: i64,
+    ) -> Result<Option<(Option<Value>, Option<Value>)>, Box<dyn Error>> {
+        const SQL_STR: &str = "SELECT payload, response_json FROM requests WHERE id = ?1";
+
+        let mut rows = self.conn.query(SQL_STR, [id]).await?;
+        match rows.next().await? {
+            Some(row) => {
+                let payload_json = self.unseal_json(row.get(0)?)?;
+                let response_json = self.unseal_json(row.get(1)?)?;
+                Ok(Some((payload_json, response_json)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Urls, headers and timing of a single request, `None` if the id doesn't exist.
+    pub async fn get_metadata(&self, id: i64) -> Result<Option<RequestMetadata>, Box<dyn Error>> {
+        const SQL_STR: &str = "SELECT full_url, qsp, server_env, request_headers, status,
+        response_headers, elapsed_ms, attempts, created_at, user, host FROM requests WHERE id = ?1";
+
+        let mut rows = self.conn.query(SQL_STR, [id]).await?;
+        let Some(row) = rows.next().await? else {
+            return Ok(None);
+        };
+
+        let status: Option<i64> = row.get(4)?;
+        let created_at: String = row.get(8)?;
+        Ok(Some(RequestMetadata {
+            full_url: row.get(0)?,
+            qsp: row.get(1)?,
+            server_env: row.get(2)?,
+            request_headers: self.unseal_json(row.get(3)?)?,
+            status: status.and_then(|status| u16::try_from(status).ok()),
+            response_headers: self.unseal_json(row.get(5)?)?,
+            elapsed_ms: row.get(6)?,
+            attempts: row.get(7)?,
+            created_at: created_at.parse::<DateTime<Utc>>()?,
+            user: row.get(9)?,
+            host: row.get(10)?,
+        }))
+    }
+
+    pub async fn get_entry_by_id(&self, id: i64) -> Result<Option<ScoobyRequest>, Box<dyn Error>> {
+        const SQL_STR: &str = "SELECT * FROM requests WHERE id = ?1";
+
+        let mut rows = self.conn.query(SQL_STR, [id]).await?;
+
+        match rows.next().await? {
+            Some(row) => {
+                let entry = self.map_to_domain(row).await?;
+                Ok(self.with_tags(vec![entry]).await?.pop())
+            }
+            None => Ok(None),
+        }
     }
 
     pub async fn get_all_entries_by_time_range(
         &self,
         time: DateTime<Utc>,
+        tags: &[String],
     ) -> Result<Vec<ScoobyRequest>, Box<dyn Error>> {
-        const SQL_STR: &str =
-            "SELECT * FROM requests WHERE created_at > ?1 ORDER BY created_at ASC";
+        let mut clauses = vec!["created_at > ?"];
+        let mut params = vec![turso::Value::Text(time.to_rfc3339())];
+        push_tag_clauses(tags, &mut clauses, &mut params);
+        let sql_str = format!(
+            "SELECT * FROM requests WHERE {} ORDER BY created_at ASC",
+            clauses.join(" AND ")
+        );
 
-        let since = time.to_rfc3339();
-
-        let mut rows = self.conn.query(SQL_STR, [since]).await?;
+        let mut rows = self.conn.query(&sql_str, params).await?;
         let mut output = Vec::new();
 
         while let Some(row) = rows.next().await? {
-            let data = match Db::map_to_domain(row).await {
-                Ok(mapped) => mapped,
-                Err(err) => {
-                    eprintln!("Something went wrong mapping data to domain: {}", err);
-                    eprintln!("Exiting...");
-                    std::process::exit(1)
-                }
-            };
-            output.push(data);
+            output.push(self.map_to_domain(row).await?);
         }
 
-        Ok(output)
+        self.with_tags(output).await
     }
 
     pub async fn get_all_entries_by_service(
         &self,
         service: String,
         time: DateTime<Utc>,
+        tags: &[String],
     ) -> Result<Vec<ScoobyRequest>, Box<dyn Error>> {
-        const SQL_STR: &str =
-            "SELECT * FROM requests WHERE service = ?1 AND created_at > ?2 ORDER BY created_at ASC";
+        let mut clauses = vec!["service = ?", "created_at > ?"];
+        let mut params = vec![
+            turso::Value::Text(service),
+            turso::Value::Text(time.to_rfc3339()),
+        ];
+        push_tag_clauses(tags, &mut clauses, &mut params);
+        let sql_str = format!(
+            "SELECT * FROM requests WHERE {} ORDER BY created_at ASC",
+            clauses.join(" AND ")
+        );
 
-        let since = time.to_rfc3339();
-
-        let mut rows = self.conn.query(SQL_STR, [service, since]).await?;
+        let mut rows = self.conn.query(&sql_str, params).await?;
         let mut output = Vec::new();
 
         while let Some(row) = rows.next().await? {
-            let data = match Db::map_to_domain(row).await {
-                Ok(mapped) => mapped,
-                Err(err) => {
-                    eprintln!("Something went wrong mapping data to domain: {}", err);
-                    eprintln!("Exiting...");
-                    std::process::exit(1)
-                }
-            };
-            output.push(data);
+            output.push(self.map_to_domain(row).await?);
         }
 
-        Ok(output)
+        self.with_tags(output).await
     }
 
-    async fn map_to_domain(row: Row) -> Result<ScoobyRequest, Box<dyn Error>> {
+    async fn map_to_domain(&self, row: Row) -> Result<ScoobyRequest, Box<dyn Error>> {
+        let id: i64 = row.get(0)?;
         let method: String = row.get(1)?;
         let service: String = row.get(2)?;
         let route_url: String = row.get(3)?;
         let url: String = row.get(4)?;
 
-        let payload_text: Option<String> = row.get(5)?;
-        let payload_json = parse_json_opt(payload_text)?;
-
-        let response_text: Option<String> = row.get(6)?;
-        let response_json = parse_json_opt(response_text)?;
+        let payload_json = self.unseal_json(row.get(5)?)?;
+        let response_json = self.unseal_json(row.get(6)?)?;
 
         let created_at_text: String = row.get(7)?;
         let created_at = created_at_text.parse::<DateTime<Utc>>()?;
 
+        let attempts: i64 = row.get(8)?;
+
+        let status: Option<i64> = row.get(9)?;
+        let status = status.and_then(|status| u16::try_from(status).ok());
+
+        let server_env: Option<String> = row.get(10)?;
+        let pinned: i64 = row.get(15)?;
+        let note: Option<String> = row.get(16)?;
+        let user: Option<String> = row.get(17)?;
+        let host: Option<String> = row.get(18)?;
+
         Ok(ScoobyRequest {
+            id,
             method,
             service,
             route_url,
@@ -295,6 +2121,115 @@ impl Db {
             payload_json,
             response_json,
             created_at,
+            attempts,
+            status,
+            server_env,
+            pinned: pinned != 0,
+            note,
+            tags: Vec::new(),
+            user,
+            host,
         })
     }
 }
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_filter() {
+        let filter = EntryFilter::parse(
+            "method:get s:user status:404 from:2025-01-01 to:2025-01-31 pinned:no tag:a tag:b",
+        )
+        .unwrap();
+        assert_eq!(filter.method.as_deref(), Some("get"));
+        assert_eq!(filter.service.as_deref(), Some("user"));
+        assert_eq!(filter.status, Some((404, 404)));
+        assert_eq!(
+            filter.from.unwrap().to_rfc3339(),
+            "2025-01-01T00:00:00+00:00"
+        );
+        // inclusive for the user, exclusive in the query
+        assert_eq!(filter.to.unwrap().to_rfc3339(), "2025-02-01T00:00:00+00:00");
+        assert_eq!(filter.pinned, Some(false));
+        assert_eq!(filter.tags, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn parses_status_classes() {
+        assert_eq!(
+            EntryFilter::parse("status:1xx").unwrap().status,
+            Some((100, 199))
+        );
+        assert_eq!(
+            EntryFilter::parse("status:5xx").unwrap().status,
+            Some((500, 599))
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_status_classes() {
+        for input in [
+            "status:0xx",
+            "status:6xx",
+            "status:655xx",
+            "status:999xx",
+            "status:xx",
+        ] {
+            assert!(
+                EntryFilter::parse(input).is_err(),
+                "{input} should not parse"
+            );
+        }
+        assert!(EntryFilter::parse("status:70000").is_err());
+    }
+
+    #[test]
+    fn lets_only_reads_through_without_writes() {
+        for sql in [
+            "SELECT * FROM requests",
+            "  -- comment\n select 1;",
+            "/* hi */ EXPLAIN QUERY PLAN SELECT * FROM requests",
+            "WITH recent AS (SELECT * FROM requests ORDER BY id DESC LIMIT 5) SELECT id FROM recent",
+            "SELECT 'delete; drop' FROM requests",
+        ] {
+            assert!(is_read_only_sql(sql), "{sql} should be allowed");
+        }
+        for sql in [
+            "DELETE FROM requests",
+            "PRAGMA query_only = 0",
+            "PRAGMA journal_mode = delete",
+            "ATTACH 'other.db' AS other",
+            "VACUUM",
+            "WITH old AS (SELECT id FROM requests) DELETE FROM requests WHERE id IN old",
+            "SELECT 1; DELETE FROM requests",
+            "",
+        ] {
+            assert!(!is_read_only_sql(sql), "{sql} should be rejected");
+        }
+    }
+
+    #[test]
+    fn skips_half_typed_tokens() {
+        let filter = EntryFilter::parse("method: status:").unwrap();
+        assert!(filter.is_empty());
+    }
+
+    #[test]
+    fn rejects_bad_tokens() {
+        for input in [
+            "method",
+            "colour:red",
+            "from:2025-13-01",
+            "to:yesterday",
+            "pinned:maybe",
+        ] {
+            assert!(
+                EntryFilter::parse(input).is_err(),
+                "{input} should not parse"
+            );
+        }
+    }
+}
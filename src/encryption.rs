@@ -0,0 +1,388 @@
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::{Mutex, OnceLock},
+};
+
+use aes_gcm::{
+    Aes256Gcm, Key, KeyInit, Nonce,
+    aead::{Aead, AeadCore, OsRng, rand_core::RngCore},
+};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use ratatui::crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    terminal,
+};
+use serde::Deserialize;
+use std::io::{IsTerminal, Write};
+
+use crate::cfg::Cfg;
+
+/// Encrypted column values start with this, anything else is read as plain text.
+pub const PREFIX: &str = "enc:v1:";
+const KEY_ENV: &str = "SCOOBY_DB_KEY";
+const PASSPHRASE_ENV: &str = "SCOOBY_DB_PASSPHRASE";
+
+/// `[encryption]` in the config file.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct EncryptionConfig {
+    /// new requests get their bodies and headers encrypted
+    pub enabled: Option<bool>,
+    /// file holding a hex key, like the one `db keygen` prints
+    pub key_file: Option<String>,
+    /// ask for a passphrase when there's no key
+    pub passphrase: Option<bool>,
+}
+
+enum Secret {
+    Key([u8; 32]),
+    Passphrase(String),
+}
+
+static CONFIG: OnceLock<EncryptionConfig> = OnceLock::new();
+/// Read once, so the ui reopening the db doesn't ask for the passphrase again.
+static SECRET: OnceLock<Result<Secret, String>> = OnceLock::new();
+/// Last passphrase derived key and the salt and params it was derived with, deriving is slow on
+/// purpose.
+static DERIVED: Mutex<Option<(String, Kdf, [u8; 32])>> = Mutex::new(None);
+
+/// Argon2id parameters a passphrase is stretched with. Stored as `kdf` next to the salt, so the
+/// cost can be raised for new databases without locking out the old ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Kdf {
+    /// memory in KiB
+    pub m_cost: u32,
+    /// iterations
+    pub t_cost: u32,
+    /// lanes
+    pub p_cost: u32,
+}
+
+impl Default for Kdf {
+    /// OWASP's recommended minimum for argon2id.
+    fn default() -> Self {
+        Kdf {
+            m_cost: 19 * 1024,
+            t_cost: 2,
+            p_cost: 1,
+        }
+    }
+}
+
+impl Display for Kdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "argon2id:m={},t={},p={}",
+            self.m_cost, self.t_cost, self.p_cost
+        )
+    }
+}
+
+impl FromStr for Kdf {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let params = s
+            .strip_prefix("argon2id:")
+            .ok_or_else(|| format!("unknown kdf `{s}`"))?;
+        let mut kdf = Kdf {
+            m_cost: 0,
+            t_cost: 0,
+            p_cost: 0,
+        };
+        for param in params.split(',') {
+            let (name, value) = param
+                .split_once('=')
+                .ok_or_else(|| format!("invalid kdf parameter `{param}`"))?;
+            let value: u32 = value
+                .parse()
+                .map_err(|_| format!("invalid kdf parameter `{param}`"))?;
+            match name {
+                "m" => kdf.m_cost = value,
+                "t" => kdf.t_cost = value,
+                "p" => kdf.p_cost = value,
+                _ => return Err(format!("unknown kdf parameter `{name}`")),
+            }
+        }
+        kdf.params()?;
+        Ok(kdf)
+    }
+}
+
+impl Kdf {
+    fn params(&self) -> Result<Params, String> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, Some(32))
+            .map_err(|err| format!("invalid kdf parameters {self}: {err}"))
+    }
+
+    fn derive_key(&self, passphrase: &str, salt: &str) -> Result<[u8; 32], String> {
+        let argon2 = Argon2::new(Algorithm::Argon2id, Version::V0x13, self.params()?);
+        let mut key = [0u8; 32];
+        argon2
+            .hash_password_into(passphrase.as_bytes(), salt.as_bytes(), &mut key)
+            .map_err(|err| format!("couldn't derive a key from the passphrase: {err}"))?;
+        Ok(key)
+    }
+}
+
+pub fn init(cfg: &Cfg) {
+    let _ = CONFIG.set(cfg.get_section("encryption").unwrap_or_default());
+}
+
+fn config() -> &'static EncryptionConfig {
+    CONFIG.get_or_init(EncryptionConfig::default)
+}
+
+pub fn enabled() -> bool {
+    config().enabled.unwrap_or(false)
+}
+
+fn parse_key(hex_key: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(hex_key.trim()).map_err(|err| format!("key isn't hex: {err}"))?;
+    bytes
+        .try_into()
+        .map_err(|_| "key has to be 32 bytes (64 hex characters)".to_string())
+}
+
+/// Random hex key for `db keygen`.
+pub fn generate_key() -> String {
+    let mut key = [0u8; 32];
+    OsRng.fill_bytes(&mut key);
+    hex::encode(key)
+}
+
+/// Random salt for a db that's getting encrypted for the first time.
+pub fn generate_salt() -> String {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    hex::encode(salt)
+}
+
+/// Reads the passphrase from the terminal without echoing it.
+fn prompt_passphrase() -> Result<String, String> {
+    if !std::io::stdin().is_terminal() {
+        return Err(format!(
+            "no terminal to ask for the passphrase, set {PASSPHRASE_ENV}"
+        ));
+    }
+    eprint!("Passphrase for the scooby history: ");
+    let _ = std::io::stderr().flush();
+
+    terminal::enable_raw_mode().map_err(|err| err.to_string())?;
+    let mut passphrase = String::new();
+    let read = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err("cancelled".to_string());
+                }
+                KeyCode::Char(c) => passphrase.push(c),
+                KeyCode::Backspace => {
+                    passphrase.pop();
+                }
+                _ => (),
+            },
+            Ok(_) => (),
+            Err(err) => break Err(err.to_string()),
+        }
+    };
+    let _ = terminal::disable_raw_mode();
+    eprintln!();
+
+    read?;
+    if passphrase.is_empty() {
+        return Err("empty passphrase".into());
+    }
+    Ok(passphrase)
+}
+
+/// `SCOOBY_DB_KEY`, then `key_file`, then `SCOOBY_DB_PASSPHRASE`, then asking if `passphrase`
+/// is set.
+fn read_secret() -> Result<Secret, String> {
+    if let Ok(key) = std::env::var(KEY_ENV) {
+        return parse_key(&key)
+            .map(Secret::Key)
+            .map_err(|err| format!("{KEY_ENV}: {err}"));
+    }
+    let config = config();
+    if let Some(path) = &config.key_file {
+        let key = std::fs::read_to_string(path)
+            .map_err(|err| format!("couldn't read key file {path}: {err}"))?;
+        return parse_key(&key)
+            .map(Secret::Key)
+            .map_err(|err| format!("key file {path}: {err}"));
+    }
+    if let Ok(passphrase) = std::env::var(PASSPHRASE_ENV) {
+        return Ok(Secret::Passphrase(passphrase));
+    }
+    if config.passphrase.unwrap_or(false) {
+        return prompt_passphrase().map(Secret::Passphrase);
+    }
+    Err(format!(
+        "no key, set {KEY_ENV}, or key_file or passphrase = true in [encryption]"
+    ))
+}
+
+/// Whether the key is a raw one, from `SCOOBY_DB_KEY` or `key_file`. Only those are the same in
+/// every db, a passphrase derived key depends on the salt of the db it was derived for.
+pub fn has_shared_key() -> bool {
+    match SECRET.get() {
+        Some(secret) => matches!(secret, Ok(Secret::Key(_))),
+        // not asking for a passphrase just to find out it's one
+        None => std::env::var(KEY_ENV).is_ok() || config().key_file.is_some(),
+    }
+}
+
+/// A fixed raw key in place of `SCOOBY_DB_KEY`, for tests that go through a db.
+#[cfg(test)]
+pub fn use_test_key() {
+    let _ = SECRET.set(Ok(Secret::Key([7; 32])));
+}
+
+/// Encrypts and decrypts column values for a db with the given salt and kdf.
+#[derive(Clone)]
+pub struct Cipher {
+    aes: Aes256Gcm,
+}
+
+impl Cipher {
+    pub fn for_salt(salt: &str, kdf: Kdf) -> Result<Cipher, String> {
+        let secret = SECRET.get_or_init(read_secret).as_ref()?;
+        let key = match secret {
+            Secret::Key(key) => *key,
+            Secret::Passphrase(passphrase) => {
+                let mut derived = DERIVED.lock().map_err(|err| err.to_string())?;
+                match derived.as_ref() {
+                    Some((derived_salt, derived_kdf, key))
+                        if derived_salt == salt && *derived_kdf == kdf =>
+                    {
+                        *key
+                    }
+                    _ => {
+                        let key = kdf.derive_key(passphrase, salt)?;
+                        *derived = Some((salt.to_string(), kdf, key));
+                        key
+                    }
+                }
+            }
+        };
+        Ok(Cipher::from_key(&key))
+    }
+
+    fn from_key(key: &[u8; 32]) -> Cipher {
+        Cipher {
+            aes: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+
+    /// `enc:v1:` and the base64 of a fresh nonce followed by the ciphertext.
+    pub fn encrypt(&self, plain: &str) -> Result<String, String> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = self
+            .aes
+            .encrypt(&nonce, plain.as_bytes())
+            .map_err(|err| format!("couldn't encrypt: {err}"))?;
+        let mut bytes = nonce.to_vec();
+        bytes.extend(sealed);
+        Ok(format!("{PREFIX}{}", STANDARD.encode(bytes)))
+    }
+
+    pub fn decrypt(&self, stored: &str) -> Result<String, String> {
+        let encoded = stored.strip_prefix(PREFIX).unwrap_or(stored);
+        let bytes = STANDARD
+            .decode(encoded)
+            .map_err(|err| format!("encrypted value isn't base64: {err}"))?;
+        if bytes.len() < 12 {
+            return Err("encrypted value is too short".into());
+        }
+        let (nonce, sealed) = bytes.split_at(12);
+        let plain = self
+            .aes
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| "couldn't decrypt, wrong key?".to_string())?;
+        String::from_utf8(plain).map_err(|err| err.to_string())
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    /// Small enough that deriving takes no time.
+    const CHEAP: Kdf = Kdf {
+        m_cost: 8,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn round_trips_through_encryption() {
+        let cipher = Cipher::from_key(&[7; 32]);
+        let sealed = cipher.encrypt(r#"{"user":"shaggy"}"#).unwrap();
+
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("shaggy"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), r#"{"user":"shaggy"}"#);
+        // fresh nonce every time
+        assert_ne!(cipher.encrypt(r#"{"user":"shaggy"}"#).unwrap(), sealed);
+    }
+
+    #[test]
+    fn wrong_key_fails_to_decrypt() {
+        let sealed = Cipher::from_key(&[7; 32]).encrypt("scooby snack").unwrap();
+        let err = Cipher::from_key(&[8; 32]).decrypt(&sealed).unwrap_err();
+        assert_eq!(err, "couldn't decrypt, wrong key?");
+    }
+
+    #[test]
+    fn rejects_mangled_values() {
+        let cipher = Cipher::from_key(&[7; 32]);
+        assert!(cipher.decrypt("enc:v1:not base64!").is_err());
+        assert!(cipher.decrypt("enc:v1:AAAA").is_err());
+    }
+
+    #[test]
+    fn passphrase_keys_depend_on_the_salt() {
+        let key = CHEAP.derive_key("zoinks", "salt-one").unwrap();
+        assert_eq!(CHEAP.derive_key("zoinks", "salt-one").unwrap(), key);
+        assert_ne!(CHEAP.derive_key("zoinks", "salt-two").unwrap(), key);
+        assert_ne!(CHEAP.derive_key("jinkies", "salt-one").unwrap(), key);
+    }
+
+    #[test]
+    fn kdf_round_trips_through_text() {
+        for kdf in [Kdf::default(), CHEAP] {
+            assert_eq!(kdf.to_string().parse::<Kdf>().unwrap(), kdf);
+        }
+        assert_eq!(CHEAP.to_string(), "argon2id:m=8,t=1,p=1");
+    }
+
+    #[test]
+    fn rejects_invalid_kdf() {
+        for kdf in [
+            "scrypt:n=1024",
+            "argon2id:m=8,t=1",
+            "argon2id:m=8,t=0,p=1",
+            "argon2id:m=1,t=1,p=1",
+            "argon2id:m=8,t=1,p=1,x=2",
+            "argon2id:m=eight,t=1,p=1",
+        ] {
+            assert!(kdf.parse::<Kdf>().is_err(), "{kdf} should be rejected");
+        }
+    }
+
+    #[test]
+    fn parses_hex_keys() {
+        assert_eq!(parse_key(&generate_key()).unwrap().len(), 32);
+        assert!(parse_key("abcd").is_err());
+        assert!(parse_key(&"zz".repeat(32)).is_err());
+    }
+}
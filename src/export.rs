@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    error::Error,
+    io::{BufRead, Write},
+};
+
+use crate::cli::ExportFormat;
+use crate::db::{Db, EncryptionParams, StoredRequest};
+
+/// Rows read from the db at a time while exporting.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// One line of an export: the row as stored plus its tags.
+#[derive(Serialize, Deserialize)]
+struct ExportedRequest {
+    #[serde(flatten)]
+    request: StoredRequest,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// First line of an export from an encrypted db. Rows stay encrypted, the importing db needs the
+/// salt and kdf to derive the same key from a passphrase.
+#[derive(Serialize, Deserialize)]
+struct ExportHeader {
+    encryption: EncryptionParams,
+}
+
+/// What `db export` did.
+pub struct ExportSummary {
+    pub exported: u64,
+    /// exact duplicates of an exported request, see `Db::fill_content_hashes`
+    pub duplicates: u64,
+}
+
+/// Writes every stored request, oldest first, after a header if the db is encrypted.
+pub async fn export(
+    db: &Db,
+    format: ExportFormat,
+    out: &mut dyn Write,
+) -> Result<ExportSummary, Box<dyn Error>> {
+    // older rows get their hash here, an import needs it to spot what's already there
+    db.fill_content_hashes().await?;
+
+    if let Some(encryption) = db.encryption_params().await? {
+        match format {
+            ExportFormat::Jsonl => writeln!(
+                out,
+                "{}",
+                serde_json::to_string(&ExportHeader { encryption })?
+            )?,
+        }
+    }
+
+    let mut exported = 0;
+    let mut after_id = 0;
+    loop {
+        let page = db.get_stored_since(after_id, EXPORT_PAGE_SIZE).await?;
+        let Some((last_id, _)) = page.last() else {
+            break;
+        };
+        after_id = *last_id;
+
+        let ids: Vec<i64> = page.iter().map(|(id, _)| *id).collect();
+        let mut tags = db.get_tags(&ids).await?;
+        for (id, request) in page {
+            let line = ExportedRequest {
+                request,
+                tags: tags.remove(&id).unwrap_or_default(),
+            };
+            match format {
+                ExportFormat::Jsonl => writeln!(out, "{}", serde_json::to_string(&line)?)?,
+            }
+            exported += 1;
+        }
+    }
+    out.flush()?;
+    Ok(ExportSummary {
+        exported,
+        duplicates: db.count_unhashed().await? as u64,
+    })
+}
+
+/// What `db import` did.
+pub struct ImportSummary {
+    pub imported: u64,
+    /// already stored, matched by content hash
+    pub skipped: u64,
+}
+
+/// Reads a JSON Lines export. The whole file is checked before anything is stored, so a broken
+/// line doesn't leave half an import behind. An encrypted export has to fit the key here, see
+/// `Db::adopt_encryption`.
+pub async fn import(db: &mut Db, input: &mut dyn BufRead) -> Result<ImportSummary, Box<dyn Error>> {
+    let mut requests = Vec::new();
+    let mut encryption = None;
+    for (index, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        if index == 0
+            && let Ok(header) = serde_json::from_str::<ExportHeader>(&line)
+        {
+            encryption = Some(header.encryption);
+            continue;
+        }
+        let exported: ExportedRequest =
+            serde_json::from_str(&line).map_err(|err| format!("line {}: {}", index + 1, err))?;
+        requests.push((exported.request, exported.tags));
+    }
+
+    if let Some(encryption) = &encryption {
+        db.adopt_encryption(encryption).await?;
+    }
+    db.fill_content_hashes().await?;
+    let imported = db.import_stored(&requests).await?;
+    Ok(ImportSummary {
+        imported,
+        skipped: requests.len() as u64 - imported,
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use crate::db::CHECK_TEXT;
+    use crate::encryption::{self, Cipher, Kdf};
+
+    /// A fresh db in the temp dir, named after the test so they can run in parallel.
+    async fn temp_db(name: &str) -> Db {
+        let path =
+            std::env::temp_dir().join(format!("scooby-export-{}-{}.db", std::process::id(), name));
+        for suffix in ["", "-wal"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+        Db::create_connection(&path.to_string_lossy())
+            .await
+            .unwrap()
+    }
+
+    /// Rows stored before `content_hash` existed, the last one an exact duplicate of the first.
+    async fn store_unhashed(db: &Db) {
+        for route in ["users", "items", "users"] {
+            let sql = format!(
+                "INSERT INTO requests (method, service, route_url, full_url, payload, response_json, created_at)
+                VALUES ('GET', 'svc', '{route}', 'http://h/svc/{route}', 'null', '{{\"ok\":true}}', '2026-10-19T00:00:00+00:00')"
+            );
+            db.run_sql(&sql, true, 0).await.unwrap();
+        }
+    }
+
+    async fn export_to_string(db: &Db) -> (String, ExportSummary) {
+        let mut out = Vec::new();
+        let summary = export(db, ExportFormat::Jsonl, &mut out).await.unwrap();
+        (String::from_utf8(out).unwrap(), summary)
+    }
+
+    #[tokio::test]
+    async fn round_trips_rows_and_tags() {
+        let source = temp_db("round-trip-source").await;
+        store_unhashed(&source).await;
+        source.add_tag(2, "team").await.unwrap();
+        let (exported, summary) = export_to_string(&source).await;
+        assert_eq!(summary.exported, 2);
+        assert_eq!(summary.duplicates, 1);
+        assert_eq!(exported.lines().count(), 2);
+
+        let mut target = temp_db("round-trip-target").await;
+        let summary = import(&mut target, &mut exported.as_bytes()).await.unwrap();
+        assert_eq!(summary.imported, 2);
+        assert_eq!(summary.skipped, 0);
+
+        let (reexported, summary) = export_to_string(&target).await;
+        assert_eq!(reexported, exported);
+        assert_eq!(summary.duplicates, 0);
+        assert!(reexported.contains(r#""tags":["team"]"#));
+    }
+
+    #[tokio::test]
+    async fn skips_what_is_already_there() {
+        let source = temp_db("skip-source").await;
+        store_unhashed(&source).await;
+        let (exported, _) = export_to_string(&source).await;
+
+        let mut target = temp_db("skip-target").await;
+        let first = exported.lines().next().unwrap();
+        import(&mut target, &mut first.as_bytes()).await.unwrap();
+        target.add_tag(1, "mine").await.unwrap();
+
+        source.add_tag(1, "theirs").await.unwrap();
+        let (exported, _) = export_to_string(&source).await;
+        let summary = import(&mut target, &mut exported.as_bytes()).await.unwrap();
+        assert_eq!(summary.imported, 1);
+        assert_eq!(summary.skipped, 1);
+        // the skipped request still gets the tags it was missing
+        let tags = target.get_tags(&[1]).await.unwrap();
+        assert_eq!(tags[&1], vec!["mine", "theirs"]);
+
+        let summary = import(&mut target, &mut exported.as_bytes()).await.unwrap();
+        assert_eq!(summary.imported, 0);
+        assert_eq!(summary.skipped, 2);
+    }
+
+    #[tokio::test]
+    async fn rejects_broken_lines_before_storing_anything() {
+        let source = temp_db("broken-source").await;
+        store_unhashed(&source).await;
+        let (exported, _) = export_to_string(&source).await;
+
+        let mut target = temp_db("broken-target").await;
+        let input = format!("{exported}{{\"method\":\"GET\"}}\n");
+        let err = import(&mut target, &mut input.as_bytes())
+            .await
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("line 3:"));
+        let (reexported, _) = export_to_string(&target).await;
+        assert_eq!(reexported, "");
+    }
+
+    #[tokio::test]
+    async fn encrypted_exports_keep_salt_and_kdf() {
+        encryption::use_test_key();
+        let salt = "00112233445566778899aabbccddeeff";
+        let kdf = Kdf {
+            m_cost: 8,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        let check = Cipher::for_salt(salt, kdf)
+            .unwrap()
+            .encrypt(CHECK_TEXT)
+            .unwrap();
+        let mut source = temp_db("encrypted-source").await;
+        source
+            .adopt_encryption(&EncryptionParams {
+                salt: salt.to_string(),
+                kdf: kdf.to_string(),
+                check,
+            })
+            .await
+            .unwrap();
+        let (exported, _) = export_to_string(&source).await;
+
+        let mut target = temp_db("encrypted-target").await;
+        import(&mut target, &mut exported.as_bytes()).await.unwrap();
+        let params = target.encryption_params().await.unwrap().unwrap();
+        assert_eq!(params.salt, salt);
+        assert_eq!(params.kdf, "argon2id:m=8,t=1,p=1");
+    }
+}
@@ -1,20 +1,31 @@
 use colored::{Color, Colorize};
 use colored_json::to_colored_json_auto;
+use reqwest::{RequestBuilder, Response, StatusCode, header::HeaderMap};
+use serde_json::{Map, Value, json};
 
-use crate::http::ResponseParts;
+use crate::bench::BenchSummary;
+use crate::cli::OutputFormat;
+use crate::db::DbStats;
+use crate::http::{ResolvedUrl, ResponseParts, outgoing_headers};
+use crate::redact::redactor;
+use crate::select::Selector;
 
-pub async fn pretty_print_response(parts: &ResponseParts) {
-    let status_color = if parts.status.is_success() {
+fn status_color(status: StatusCode) -> Color {
+    if status.is_success() {
         Color::Green
-    } else if parts.status.is_client_error() {
+    } else if status.is_client_error() {
         Color::Yellow
     } else {
         Color::Red
-    };
+    }
+}
+
+pub async fn pretty_print_response(parts: &ResponseParts) {
+    let status_color = status_color(parts.status);
 
     println!(
         "{} {}",
-        "HTTP/1.1".bold(),
+        format!("{:?}", parts.version).bold(),
         parts.status.to_string().color(status_color).bold()
     );
 
@@ -28,13 +39,300 @@ pub async fn pretty_print_response(parts: &ResponseParts) {
 
     println!();
 
-    let colored_output = match to_colored_json_auto(&parts.body) {
-        Ok(some) => some,
+    print_body(parts);
+}
+
+/// Json bodies get coloured, anything else is printed as it came.
+fn print_body(parts: &ResponseParts) {
+    match &parts.body {
+        Value::Null | Value::String(_) => println!("{}", parts.raw),
+        body => print_colored_json(body),
+    }
+}
+
+pub async fn print_response(parts: &ResponseParts, format: OutputFormat, attempts: usize) {
+    match format {
+        OutputFormat::Pretty => pretty_print_response(parts).await,
+        OutputFormat::Body => print_body(parts),
+        OutputFormat::Raw => println!("{}", parts.raw),
+        OutputFormat::Headers => {
+            println!("{:?} {}", parts.version, parts.status);
+            for (key, value) in parts.headers.iter() {
+                println!("{}: {}", key, value.to_str().unwrap_or_default());
+            }
+        }
+        OutputFormat::Status => println!("{}", parts.status.as_u16()),
+        OutputFormat::Json => print_colored_json(&response_envelope(parts, attempts)),
+        OutputFormat::None => (),
+    }
+}
+
+/// Evaluates the selector and prints every match on its own, like jq does.
+pub fn print_selected(selector: &Selector, value: &Value) -> Result<(), String> {
+    for selected in selector.eval(value)? {
+        print_colored_json(&selected);
+    }
+    Ok(())
+}
+
+fn print_colored_json(value: &Value) {
+    match to_colored_json_auto(value) {
+        Ok(output) => println!("{}", output),
         Err(err) => {
             eprintln!("Couldn't produce pretty output: {}", err);
             std::process::exit(1)
         }
+    }
+}
+
+/// Headers as a json object, repeated headers become arrays.
+fn headers_to_json(headers: &HeaderMap) -> Value {
+    let mut map = Map::new();
+    for key in headers.keys() {
+        let mut values: Vec<Value> = headers
+            .get_all(key)
+            .iter()
+            .map(|value| Value::String(value.to_str().unwrap_or_default().to_string()))
+            .collect();
+        let value = if values.len() == 1 {
+            values.remove(0)
+        } else {
+            Value::Array(values)
+        };
+        map.insert(key.as_str().to_string(), value);
+    }
+    Value::Object(map)
+}
+
+fn response_envelope(parts: &ResponseParts, attempts: usize) -> Value {
+    json!({
+        "status": parts.status.as_u16(),
+        "headers": headers_to_json(&parts.headers),
+        "body": parts.body,
+        "timing": {
+            "total_ms": parts.elapsed.as_millis() as u64,
+            "attempts": attempts,
+        },
+    })
+}
+
+pub fn pretty_print_bench_summary(summary: &BenchSummary) {
+    println!(
+        "{} {} {}",
+        "BENCH".bold(),
+        summary.method.purple(),
+        summary.url.yellow()
+    );
+    println!(
+        "{}: {} requests, {} concurrent, {:.2}s",
+        "run".cyan(),
+        summary.requests,
+        summary.concurrency,
+        summary.duration.as_secs_f64()
+    );
+    println!("{}: {:.1} req/s", "throughput".cyan(), summary.throughput);
+    println!(
+        "{}: p50 {:.1}ms, p90 {:.1}ms, p99 {:.1}ms",
+        "latency".cyan(),
+        summary.p50_ms,
+        summary.p90_ms,
+        summary.p99_ms
+    );
+
+    println!("{}:", "statuses".cyan());
+    for (status, count) in summary.statuses.iter() {
+        let label = match StatusCode::from_u16(*status) {
+            Ok(code) => code.to_string().color(status_color(code)).bold(),
+            Err(_) => status.to_string().red().bold(),
+        };
+        println!("  {}: {}", label, count);
+    }
+
+    if !summary.errors.is_empty() {
+        println!("{}:", "errors".red());
+        for (kind, count) in summary.errors.iter() {
+            println!("  {}: {}", kind.red(), count);
+        }
+    }
+}
+
+/// `1.4 MB` style, powers of 1024 like `du -h`.
+fn human_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
+
+pub fn pretty_print_db_stats(stats: &DbStats) {
+    let size = stats
+        .file_bytes
+        .map(|bytes| format!(" ({})", human_bytes(bytes)))
+        .unwrap_or_default();
+    println!("{} {}{}", "DB".bold(), stats.path.yellow(), size);
+    println!(
+        "{}: {} ({} pinned, {} tagged)",
+        "requests".cyan(),
+        stats.requests,
+        stats.pinned,
+        stats.tagged
+    );
+    println!("{}: {}", "attempts".cyan(), stats.attempts);
+    println!("{}: {}", "bench runs".cyan(), stats.bench_runs);
+    if let Some((id, created_at)) = stats.oldest {
+        println!("{}: #{} at {}", "oldest".cyan(), id, created_at);
+    }
+    if let Some((id, created_at)) = stats.newest {
+        println!("{}: #{} at {}", "newest".cyan(), id, created_at);
+    }
+
+    if stats.services.is_empty() {
+        return;
+    }
+    println!("{}:", "by service".cyan());
+    let width = stats
+        .services
+        .iter()
+        .map(|service| service.service.len())
+        .max()
+        .unwrap_or(0);
+    for service in &stats.services {
+        println!(
+            "  {:<width$}  {:>6} requests  {:>9} of bodies  since {}",
+            service.service.green(),
+            service.requests,
+            human_bytes(service.body_bytes.max(0) as u64),
+            service.oldest.format("%Y-%m-%d"),
+            width = width
+        );
+    }
+}
+
+/// Same as `headers_to_json` but redacted, this is what ends up in the db.
+pub fn masked_headers_to_json(headers: &HeaderMap) -> Value {
+    let mut map = Map::new();
+    for key in headers.keys() {
+        let mut values: Vec<Value> = headers
+            .get_all(key)
+            .iter()
+            .map(|value| Value::String(redactor().header_value(key, value)))
+            .collect();
+        let value = if values.len() == 1 {
+            values.remove(0)
+        } else {
+            Value::Array(values)
+        };
+        map.insert(key.as_str().to_string(), value);
+    }
+    Value::Object(map)
+}
+
+fn print_verbose_headers(prefix: &str, headers: &HeaderMap) {
+    for (key, value) in headers.iter() {
+        eprintln!(
+            "{} {}: {}",
+            prefix,
+            key.as_str().cyan(),
+            redactor().header_value(key, value)
+        );
+    }
+}
+
+/// `-v`, shows how the request url was put together.
+pub fn print_verbose_url(resolved: &ResolvedUrl) {
+    eprintln!("{}: {}", "domain template".cyan(), resolved.domain_template);
+    eprintln!(
+        "{}: [SERVER_ENV] -> {} = {}",
+        "server env".cyan(),
+        resolved.server_env,
+        resolved.base_url
+    );
+    eprintln!("{}: {:?}", "dev prefix".cyan(), resolved.dev_prefix);
+    eprintln!(
+        "{}: {} ({})",
+        "service".cyan(),
+        resolved.service_name,
+        resolved.service_url
+    );
+    let redactor = redactor();
+    eprintln!("{}: {}", "route".cyan(), redactor.text(&resolved.route_url));
+    eprintln!(
+        "{}: {:?}",
+        "querystring".cyan(),
+        redactor.text(&resolved.qsp)
+    );
+    eprintln!(
+        "{}: {}",
+        "url".cyan(),
+        redactor.text(&resolved.url).purple()
+    );
+    eprintln!();
+}
+
+/// `-vv`, prints the request line, headers and body as they go on the wire.
+pub fn print_verbose_request(req_builder: &RequestBuilder) {
+    let request = match req_builder.try_clone().map(|clone| clone.build()) {
+        Some(Ok(request)) => request,
+        Some(Err(err)) => {
+            eprintln!("Couldn't build request for verbose output: {}", err);
+            return;
+        }
+        None => return,
     };
 
-    println!("{}", colored_output);
+    let url = request.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    eprintln!(
+        "{} {} {} {:?}",
+        ">".bold(),
+        request.method().as_str().purple(),
+        redactor().text(&target),
+        request.version()
+    );
+    eprintln!(
+        "> {}: {}",
+        "host".cyan(),
+        url.host_str().unwrap_or_default()
+    );
+
+    print_verbose_headers(">", &outgoing_headers(&request));
+    eprintln!(">");
+
+    if let Some(bytes) = request.body().and_then(|body| body.as_bytes()) {
+        match serde_json::from_slice::<serde_json::Value>(bytes) {
+            Ok(mut json) => {
+                redactor().json(&mut json);
+                eprintln!(
+                    "{}",
+                    to_colored_json_auto(&json).unwrap_or_else(|_| "<invalid json>".into())
+                )
+            }
+            Err(_) => eprintln!("{}", redactor().text(&String::from_utf8_lossy(bytes))),
+        }
+    }
+    eprintln!();
+}
+
+/// `-vv`, prints the response status line and headers before the body is read.
+pub fn print_verbose_response_head(res: &Response) {
+    eprintln!(
+        "{} {:?} {}",
+        "<".bold(),
+        res.version(),
+        res.status().to_string().color(status_color(res.status()))
+    );
+    print_verbose_headers("<", res.headers());
+    eprintln!("<");
+    eprintln!();
 }
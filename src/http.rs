@@ -1,14 +1,215 @@
-use reqwest::{Client, Response, StatusCode, header::HeaderMap};
+use colored::Colorize;
+use reqwest::{
+    Certificate, Client, Identity, Proxy, Request, RequestBuilder, Response, StatusCode, Version,
+    header::HeaderMap, redirect::Policy,
+};
+use serde::Deserialize;
 use serde_json::Value;
-use std::error::Error;
+use std::{
+    error::Error,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::cfg::Cfg;
+use crate::cli::{HttpArgs, Redirects, TargetArgs};
+use crate::retry::{Attempt, RetryPolicy};
 
 pub struct ResponseParts {
     pub status: StatusCode,
+    pub version: Version,
     pub headers: HeaderMap,
+    /// parsed json, a string when the body isn't json and null when there's no body
     pub body: Value,
+    /// body exactly as it came over the wire
+    pub raw: String,
+    /// from sending the first attempt until the body was read, retries included
+    pub elapsed: Duration,
+}
+
+/// `[http]` in the config file, also used for `[services.<service>.http]` overrides.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct HttpConfig {
+    pub timeout_secs: Option<u64>,
+    pub connect_timeout_secs: Option<u64>,
+    pub redirects: Option<String>,
+    pub proxy: Option<String>,
+    pub ca_bundle: Option<String>,
+    pub client_cert: Option<String>,
+    pub client_key: Option<String>,
+    pub insecure: Option<bool>,
 }
 
-pub fn create_http_client(timeout_secs: u64) -> Client {
+impl HttpConfig {
+    fn merge(self, other: HttpConfig) -> HttpConfig {
+        HttpConfig {
+            timeout_secs: other.timeout_secs.or(self.timeout_secs),
+            connect_timeout_secs: other.connect_timeout_secs.or(self.connect_timeout_secs),
+            redirects: other.redirects.or(self.redirects),
+            proxy: other.proxy.or(self.proxy),
+            ca_bundle: other.ca_bundle.or(self.ca_bundle),
+            client_cert: other.client_cert.or(self.client_cert),
+            client_key: other.client_key.or(self.client_key),
+            insecure: other.insecure.or(self.insecure),
+        }
+    }
+}
+
+/// Effective http client settings after config and cli have been merged.
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub timeout_secs: u64,
+    pub connect_timeout_secs: Option<u64>,
+    pub redirects: Redirects,
+    pub proxy: Option<String>,
+    pub ca_bundle: Option<String>,
+    pub client_cert: Option<(String, String)>,
+    pub insecure: bool,
+}
+
+impl HttpSettings {
+    /// Resolves the settings for a service: defaults, then `[http]`, then
+    /// `[services.<service>.http]`, then whatever was given on the command line.
+    pub fn resolve(cfg: &Cfg, service: &str, cli: &HttpArgs) -> Self {
+        let global: HttpConfig = cfg.get_section("http").unwrap_or_default();
+        let per_service: HttpConfig = cfg.get_service_section(service, "http").unwrap_or_default();
+        let merged = global.merge(per_service);
+
+        let redirects = match cli.redirects {
+            Some(redirects) => redirects,
+            None => match merged.redirects.as_deref().map(str::parse) {
+                Some(Ok(redirects)) => redirects,
+                Some(Err(err)) => {
+                    eprintln!("Invalid redirects value in config: {}", err);
+                    std::process::exit(1)
+                }
+                None => Redirects::Follow,
+            },
+        };
+
+        let client_cert = match (
+            cli.client_cert.clone().or(merged.client_cert),
+            cli.client_key.clone().or(merged.client_key),
+        ) {
+            (Some(cert), Some(key)) => Some((cert, key)),
+            (None, None) => None,
+            _ => {
+                eprintln!("Client certificate and key have to be configured together");
+                std::process::exit(1)
+            }
+        };
+
+        HttpSettings {
+            // longish timeout, the apis are quite slow sometimes...
+            timeout_secs: cli.timeout_secs.or(merged.timeout_secs).unwrap_or(15),
+            connect_timeout_secs: cli.connect_timeout_secs.or(merged.connect_timeout_secs),
+            redirects,
+            proxy: cli.proxy.clone().or(merged.proxy),
+            ca_bundle: cli.ca_bundle.clone().or(merged.ca_bundle),
+            client_cert,
+            insecure: cli.insecure.or(merged.insecure).unwrap_or(false),
+        }
+    }
+}
+
+impl Display for HttpSettings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unset = || "-".to_string();
+
+        writeln!(f, "{}: {}s", "timeout".cyan(), self.timeout_secs)?;
+        writeln!(
+            f,
+            "{}: {}",
+            "connect timeout".cyan(),
+            self.connect_timeout_secs
+                .map(|secs| format!("{secs}s"))
+                .unwrap_or_else(unset)
+        )?;
+        writeln!(f, "{}: {}", "redirects".cyan(), self.redirects)?;
+        writeln!(
+            f,
+            "{}: {}",
+            "proxy".cyan(),
+            self.proxy.clone().unwrap_or_else(unset)
+        )?;
+        writeln!(
+            f,
+            "{}: {}",
+            "ca bundle".cyan(),
+            self.ca_bundle.clone().unwrap_or_else(unset)
+        )?;
+        writeln!(
+            f,
+            "{}: {}",
+            "client cert".cyan(),
+            self.client_cert
+                .as_ref()
+                .map(|(cert, key)| format!("{cert} (key {key})"))
+                .unwrap_or_else(unset)
+        )?;
+        if self.insecure {
+            writeln!(f, "{}: {}", "insecure".cyan(), "yes".red().bold())?;
+        } else {
+            writeln!(f, "{}: no", "insecure".cyan())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn read_file_or_exit(path: &str, what: &str) -> Vec<u8> {
+    match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("Couldn't read {} from {}: {}", what, path, err);
+            std::process::exit(1)
+        }
+    }
+}
+
+/// All the parts a request url is built from, kept around for verbose output.
+#[derive(Debug, Clone)]
+pub struct ResolvedUrl {
+    pub domain_template: String,
+    pub server_env: String,
+    pub base_url: String,
+    pub dev_prefix: String,
+    pub service_name: String,
+    pub service_url: String,
+    pub route_url: String,
+    pub qsp: String,
+    pub url: String,
+}
+
+/// Builds the final request url out of the config and cli parts.
+pub fn resolve_url(cfg: &Cfg, target: &TargetArgs) -> ResolvedUrl {
+    // service url parts are stored in config data, user gets to choose the option to use
+    let service_name = cfg.get(&target.service);
+    let dev_prefix = cfg.get(&target.dev_prefix);
+    let service_url = format!("{}{}", dev_prefix, service_name);
+    let domain_template = cfg.get(&target.domain_url);
+    let server_env = target.server_env.as_ref().to_string();
+
+    let base_url = domain_template.replace("[SERVER_ENV]", &server_env);
+    let qsp = target.qsp.clone().unwrap_or_default();
+
+    let url = format!("{}{}/{}{}", base_url, service_url, target.route_url, qsp);
+
+    ResolvedUrl {
+        domain_template,
+        server_env,
+        base_url,
+        dev_prefix,
+        service_name,
+        service_url,
+        route_url: target.route_url.clone(),
+        qsp,
+        url,
+    }
+}
+
+/// Headers sent with every request, the client adds these when the request is executed.
+pub fn default_headers() -> HeaderMap {
     let cookie_value = match std::env::var("auth_token") {
         Ok(token) => token,
         Err(err) => {
@@ -17,18 +218,87 @@ pub fn create_http_client(timeout_secs: u64) -> Client {
         }
     };
 
-    let http_client = Client::builder()
-        .timeout(std::time::Duration::from_secs(timeout_secs))
-        .default_headers({
-            let mut headers = reqwest::header::HeaderMap::new();
-            if let Ok(parsed_value) = cookie_value.parse() {
-                headers.insert("Cookie", parsed_value);
-            }
-            headers
+    let mut headers = reqwest::header::HeaderMap::new();
+    if let Ok(parsed_value) = cookie_value.parse() {
+        headers.insert("Cookie", parsed_value);
+    }
+    headers
+}
+
+/// Headers of a built request as they go out, client default headers are only merged in
+/// when the request is executed.
+pub fn outgoing_headers(request: &Request) -> HeaderMap {
+    let mut headers = request.headers().clone();
+    for (key, value) in default_headers().iter() {
+        if !headers.contains_key(key) {
+            headers.insert(key.clone(), value.clone());
+        }
+    }
+    headers
+}
+
+/// `outgoing_headers` straight from a builder, empty if the request can't be built.
+pub fn builder_headers(req_builder: &RequestBuilder) -> HeaderMap {
+    match req_builder.try_clone().map(|clone| clone.build()) {
+        Some(Ok(request)) => outgoing_headers(&request),
+        _ => HeaderMap::new(),
+    }
+}
+
+pub fn create_http_client(settings: &HttpSettings) -> Client {
+    let mut client_builder = Client::builder()
+        .timeout(Duration::from_secs(settings.timeout_secs))
+        .redirect(match settings.redirects {
+            Redirects::Follow => Policy::default(),
+            Redirects::None => Policy::none(),
+            Redirects::Max(hops) => Policy::limited(hops),
         })
-        .build();
+        .danger_accept_invalid_certs(settings.insecure)
+        .danger_accept_invalid_hostnames(settings.insecure)
+        .default_headers(default_headers());
+
+    if let Some(secs) = settings.connect_timeout_secs {
+        client_builder = client_builder.connect_timeout(Duration::from_secs(secs));
+    }
+
+    if let Some(proxy_url) = &settings.proxy {
+        match Proxy::all(proxy_url) {
+            Ok(proxy) => client_builder = client_builder.proxy(proxy),
+            Err(err) => {
+                eprintln!("Invalid proxy url {}: {}", proxy_url, err);
+                std::process::exit(1)
+            }
+        }
+    }
+
+    if let Some(path) = &settings.ca_bundle {
+        let pem = read_file_or_exit(path, "CA bundle");
+        match Certificate::from_pem_bundle(&pem) {
+            Ok(certs) => {
+                for cert in certs {
+                    client_builder = client_builder.add_root_certificate(cert);
+                }
+            }
+            Err(err) => {
+                eprintln!("Couldn't parse CA bundle {}: {}", path, err);
+                std::process::exit(1)
+            }
+        }
+    }
+
+    if let Some((cert_path, key_path)) = &settings.client_cert {
+        let cert = read_file_or_exit(cert_path, "client certificate");
+        let key = read_file_or_exit(key_path, "client key");
+        match Identity::from_pkcs8_pem(&cert, &key) {
+            Ok(identity) => client_builder = client_builder.identity(identity),
+            Err(err) => {
+                eprintln!("Couldn't load client certificate: {}", err);
+                std::process::exit(1)
+            }
+        }
+    }
 
-    match http_client {
+    match client_builder.build() {
         Ok(client) => client,
         Err(err) => {
             eprintln!("Couldn't construct an http client instance {}", err);
@@ -37,14 +307,108 @@ pub fn create_http_client(timeout_secs: u64) -> Client {
     }
 }
 
-pub async fn split_http_response(res: Response) -> Result<ResponseParts, Box<dyn Error>> {
+pub async fn split_http_response(
+    res: Response,
+    started: Instant,
+) -> Result<ResponseParts, Box<dyn Error>> {
     let status = res.status();
+    let version = res.version();
     let headers = res.headers().clone();
-    let body: serde_json::Value = res.json().await?;
+    let raw = res.text().await?;
+    let elapsed = started.elapsed();
+    // html error pages and 204s are still responses worth showing and storing
+    let body = if raw.trim().is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_str(&raw).unwrap_or_else(|_| Value::String(raw.clone()))
+    };
 
     Ok(ResponseParts {
         status,
+        version,
         headers,
         body,
+        raw,
+        elapsed,
     })
 }
+
+/// Sends the request, retrying according to the policy. Every try is returned as an `Attempt`,
+/// alongside the result of the last one.
+pub async fn send_with_retry(
+    req_builder: RequestBuilder,
+    policy: &RetryPolicy,
+) -> (Result<Response, reqwest::Error>, Vec<Attempt>) {
+    let mut attempts: Vec<Attempt> = Vec::new();
+    let mut retry: u32 = 0;
+
+    loop {
+        // bodies we send are plain json, so cloning only fails for streams which we never use
+        let (current, next) = match req_builder.try_clone() {
+            Some(clone) if retry < policy.count => (clone, true),
+            Some(clone) => (clone, false),
+            None => {
+                let started = Instant::now();
+                let result = req_builder.send().await;
+                attempts.push(attempt_from(1, &result, started));
+                return (result, attempts);
+            }
+        };
+
+        let started = Instant::now();
+        let result = current.send().await;
+        let mut attempt = attempt_from(retry + 1, &result, started);
+
+        let delay = match &result {
+            Ok(res) if next && policy.is_retryable_status(res.status().as_u16()) => Some(
+                policy
+                    .retry_after(res.headers())
+                    .unwrap_or_else(|| policy.backoff(retry)),
+            ),
+            Err(err) if next && policy.is_retryable_error(err) => Some(policy.backoff(retry)),
+            _ => None,
+        };
+
+        match delay {
+            Some(delay) => {
+                attempt.delay_ms = delay.as_millis() as u64;
+                eprintln!(
+                    "Attempt {} failed ({}), retrying in {}ms",
+                    attempt.number,
+                    attempt
+                        .status
+                        .map(|status| status.to_string())
+                        .or_else(|| attempt.error.clone())
+                        .unwrap_or_default(),
+                    attempt.delay_ms
+                );
+                attempts.push(attempt);
+                tokio::time::sleep(delay).await;
+                retry += 1;
+            }
+            None => {
+                attempts.push(attempt);
+                return (result, attempts);
+            }
+        }
+    }
+}
+
+fn attempt_from(
+    number: u32,
+    result: &Result<Response, reqwest::Error>,
+    started: Instant,
+) -> Attempt {
+    let (status, error) = match result {
+        Ok(res) => (Some(res.status().as_u16()), None),
+        Err(err) => (None, Some(err.to_string())),
+    };
+
+    Attempt {
+        number,
+        status,
+        error,
+        elapsed_ms: started.elapsed().as_millis() as u64,
+        delay_ms: 0,
+    }
+}
@@ -4,104 +4,193 @@
 #![deny(clippy::unimplemented)]
 #![deny(clippy::dbg_macro)]
 
+mod bench;
 mod cfg;
 mod cli;
 mod db;
+mod encryption;
+mod export;
 mod formatting;
 mod http;
+mod redact;
+mod remote;
+mod retention;
+mod retry;
+mod select;
 mod ui;
+mod workspace;
 
 use chrono::{DateTime, NaiveDate, Utc};
 use clap::Parser;
 use colored::Colorize;
+use std::{io::IsTerminal, time::Instant};
 use tokio::fs;
 
-use formatting::pretty_print_response;
+use formatting::{
+    pretty_print_bench_summary, pretty_print_db_stats, print_response, print_selected,
+    print_verbose_request, print_verbose_response_head, print_verbose_url,
+};
 
+use bench::BenchArgs;
 use cfg::Cfg;
-use db::{Db, DbStoreArgs};
-use http::{create_http_client, split_http_response};
+use db::{Db, DbStoreArgs, EntryFilter};
+use http::{
+    HttpSettings, ResolvedUrl, builder_headers, create_http_client, resolve_url, send_with_retry,
+    split_http_response,
+};
+use retention::RetentionPolicy;
+use retry::RetryPolicy;
 
-use cli::{DbCommand, ModeType, ReqCommand, ScoobyArgs};
+use cli::{BenchCommand, DbCommand, ModeType, OutputFormat, ReqCommand, ScoobyArgs};
 
-use ui::Ui;
+use ui::{Ui, UiConfig};
 
-async fn handle_req_mode(cli: ReqCommand, cfg: Cfg) {
-    let db = match Db::create_connection().await {
+async fn read_json_payload(path: String) -> serde_json::Value {
+    let payload = match fs::read_to_string(path).await {
+        Ok(payload) => payload,
+        Err(err) => {
+            eprintln!("No (valid) json payload in provided path: {}", err);
+            std::process::exit(1)
+        }
+    };
+
+    match serde_json::from_str(&payload) {
+        Ok(json) => json,
+        Err(err) => {
+            eprintln!("Couldn't read json from payload: {}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn resolve_retention(cfg: &Cfg) -> RetentionPolicy {
+    match RetentionPolicy::resolve(cfg) {
+        Ok(policy) => policy,
+        Err(err) => {
+            eprintln!("Couldn't read [retention]: {}", err);
+            std::process::exit(1)
+        }
+    }
+}
+
+/// Asks on stdin, refuses when there's nobody to ask.
+fn confirm_delete(count: usize) -> bool {
+    if !std::io::stdin().is_terminal() {
+        eprintln!("Pass --yes to delete {} requests without asking", count);
+        std::process::exit(1)
+    }
+    print!("Delete {} requests? [y/N] ", count);
+    let _ = std::io::Write::flush(&mut std::io::stdout());
+    let mut answer = String::new();
+    let _ = std::io::stdin().read_line(&mut answer);
+    matches!(answer.trim(), "y" | "Y" | "yes")
+}
+
+fn print_deleted(deleted: u64) {
+    match deleted {
+        1 => println!("Deleted 1 request"),
+        deleted => println!("Deleted {} requests", deleted),
+    }
+}
+
+/// Opens the db or exits, for the modes that can't do anything without it.
+async fn open_db(db_path: &str) -> Db {
+    match Db::create_connection(db_path).await {
         Ok(db) => db,
         Err(err) => {
             eprintln!("{}: {}", Colorize::red("Ruh roh, db isn't working"), err);
             eprintln!("Exiting...");
             std::process::exit(1)
         }
-    };
-
-    // service url parts are stored in config data, user gets to choose the option to use
-    let service_name = cfg.get(&cli.service);
-    let service_url = format!("{}{}", cfg.get(&cli.dev_prefix), service_name);
-    let domain_url = cfg.get(&cli.domain_url);
+    }
+}
 
-    let base_url = domain_url.replace("[SERVER_ENV]", cli.server_env.as_ref());
+async fn handle_req_mode(cli: ReqCommand, cfg: Cfg, db_path: &str) {
+    // a broken db or a wrong key shows up before anything is sent, but the db isn't held
+    // open through the request
+    drop(open_db(db_path).await);
+    let retention = resolve_retention(&cfg);
 
-    let url = format!(
-        "{}{}/{}{}",
-        base_url,
-        service_url,
-        cli.route_url,
-        cli.qsp.unwrap_or_default()
-    );
-    println!("\nRequesting: {}\n", url.purple());
+    let resolved_url = resolve_url(&cfg, &cli.target);
+    let ResolvedUrl {
+        service_name, url, ..
+    } = resolved_url.clone();
+    // keep stdout clean for anything else than the default output, it's likely piped somewhere
+    let chatty = cli.output == OutputFormat::Pretty && cli.select.is_none();
+    if chatty {
+        println!("\nRequesting: {}\n", url.purple());
+    }
 
-    // longish timeout, the apis are quite slow sometimes...
-    let timeout_secs: u64 = 15;
-    let http_client = create_http_client(timeout_secs);
+    let http_settings = HttpSettings::resolve(&cfg, &cli.target.service, &cli.http);
+    if cli.verbose > 0 {
+        print_verbose_url(&resolved_url);
+        eprintln!("{}", http_settings);
+    }
+    let http_client = create_http_client(&http_settings);
 
     let mut req_builder = http_client.request(cli.method.clone(), url.clone());
     let mut json_payload: Option<serde_json::Value> = None;
 
     if let Some(path) = cli.payload_path {
-        let payload = match fs::read_to_string(path).await {
-            Ok(payload) => payload,
-            Err(err) => {
-                eprintln!("No (valid) json payload in provided path: {}", err);
-                std::process::exit(1)
-            }
-        };
-
-        let json: serde_json::Value = match serde_json::from_str(&payload) {
-            Ok(json) => json,
-            Err(err) => {
-                eprintln!("Couldn't read json from payload: {}", err);
-                std::process::exit(1);
-            }
-        };
-
+        let json = read_json_payload(path).await;
         req_builder = req_builder.json(&json);
         json_payload = Some(json);
     }
 
-    let response = req_builder.send().await;
+    if cli.verbose > 1 {
+        print_verbose_request(&req_builder);
+    }
+
+    let request_headers = builder_headers(&req_builder);
+    let retry_policy = RetryPolicy::resolve(&cfg, &cli.target.service, cli.retry);
+    let started = Instant::now();
+    let (response, attempts) = send_with_retry(req_builder, &retry_policy).await;
+    let attempt_count = attempts.len();
+    if attempt_count > 1 && chatty {
+        println!("Took {} attempts\n", attempt_count);
+    }
+
+    let db_store_args = DbStoreArgs {
+        method: cli.method.to_string(),
+        service: service_name.to_string(),
+        url,
+        route_url: cli.target.route_url,
+        payload: json_payload,
+        attempts,
+        server_env: Some(resolved_url.server_env),
+        qsp: Some(resolved_url.qsp),
+        request_headers,
+        tags: cli.tags,
+    };
 
     match response {
         Ok(res) => {
-            let parts = match split_http_response(res).await {
+            if cli.verbose > 1 {
+                print_verbose_response_head(&res);
+            }
+
+            let parts = match split_http_response(res, started).await {
                 Ok(parts) => parts,
                 Err(err) => {
-                    eprintln!("Couldn't parse response: {}", err);
+                    eprintln!("Couldn't read response: {}", err);
                     std::process::exit(1)
                 }
             };
 
-            pretty_print_response(&parts).await;
-
-            let db_store_args = DbStoreArgs {
-                method: cli.method.to_string(),
-                service: service_name.to_string(),
-                url,
-                route_url: cli.route_url,
-                payload: json_payload,
+            // the request is still stored when selecting fails, the exit code tells about it
+            let selected = match &cli.select {
+                Some(selector) => print_selected(selector, &parts.body),
+                None => {
+                    print_response(&parts, cli.output, attempt_count).await;
+                    Ok(())
+                }
             };
+            if let Err(err) = &selected {
+                eprintln!("Couldn't select from response: {}", err);
+            }
 
+            // opened only now, the file is locked while it's open and `db ui` polls it
+            let db = open_db(db_path).await;
             match db.insert_args(db_store_args, parts).await {
                 Ok(_) => (),
                 Err(err) => {
@@ -109,15 +198,116 @@ async fn handle_req_mode(cli: ReqCommand, cfg: Cfg) {
                     std::process::exit(1)
                 }
             };
+
+            // the request itself went fine, so a failed cleanup is only worth a warning
+            if let Err(err) = db.apply_retention(&retention).await {
+                eprintln!("Couldn't prune old requests: {}", err);
+            }
+
+            if selected.is_err() {
+                std::process::exit(1)
+            }
         }
         Err(err) => {
             eprintln!("{}: {}", Colorize::red("Ruh roh, request errored!"), err);
+            // kept with its attempts, a retry run that never got through is worth looking at too
+            let db = open_db(db_path).await;
+            if let Err(err) = db.insert_failed(db_store_args, started.elapsed()).await {
+                eprintln!("Inserting data to db failed: {}", err);
+            }
             eprintln!("Exiting...");
             std::process::exit(1)
         }
     }
 }
 
+async fn handle_bench_mode(cli: BenchCommand, cfg: Cfg, db_path: &str) {
+    if cli.requests == 0 {
+        eprintln!("Nothing to bench, request count has to be at least 1");
+        std::process::exit(1)
+    }
+
+    let ResolvedUrl {
+        service_name, url, ..
+    } = resolve_url(&cfg, &cli.target);
+    println!(
+        "\nBenching: {} ({} requests, {} concurrent)\n",
+        url.purple(),
+        cli.requests,
+        cli.concurrency
+    );
+
+    let payload = match cli.payload_path {
+        Some(path) => Some(read_json_payload(path).await),
+        None => None,
+    };
+
+    let http_settings = HttpSettings::resolve(&cfg, &cli.target.service, &cli.http);
+    let http_client = create_http_client(&http_settings);
+
+    let summary = bench::run(
+        http_client,
+        BenchArgs {
+            method: cli.method,
+            service: service_name,
+            url,
+            payload,
+            requests: cli.requests,
+            concurrency: cli.concurrency,
+        },
+    )
+    .await;
+
+    pretty_print_bench_summary(&summary);
+
+    if cli.store {
+        let db = open_db(db_path).await;
+
+        if let Err(err) = db.insert_bench_summary(&summary).await {
+            eprintln!("Inserting bench summary to db failed: {}", err);
+            std::process::exit(1)
+        }
+    }
+}
+
+async fn set_pinned(db: &Db, id: i64, pinned: bool) {
+    match db.set_pinned(id, pinned).await {
+        Ok(true) if pinned => println!("Pinned #{}", id),
+        Ok(true) => println!("Unpinned #{}", id),
+        Ok(false) => {
+            eprintln!("No request stored with id {}", id);
+            std::process::exit(1)
+        }
+        Err(err) => {
+            eprintln!("Couldn't update the entry: {}", err);
+            std::process::exit(1)
+        }
+    }
+}
+
+fn print_workspaces(db_path: &str) {
+    let names = match workspace::list_workspaces() {
+        Ok(names) => names,
+        Err(err) => {
+            eprintln!("Couldn't list workspaces: {}", err);
+            std::process::exit(1)
+        }
+    };
+    let mut in_use = false;
+    for name in names {
+        let path = workspace::workspace_path(&name).unwrap_or_default();
+        if path == db_path {
+            in_use = true;
+            println!("{} {}", "*".green(), name.green());
+        } else {
+            println!("  {}", name);
+        }
+    }
+    if !in_use {
+        println!("{} {}", "*".green(), db_path.green());
+    }
+}
+
 fn date_to_utc_start(s: String) -> Result<DateTime<Utc>, chrono::ParseError> {
     let date = NaiveDate::parse_from_str(s.as_str(), "%Y-%m-%d")?;
     let date_time =
@@ -125,15 +315,18 @@ fn date_to_utc_start(s: String) -> Result<DateTime<Utc>, chrono::ParseError> {
     Ok(date_time)
 }
 
-async fn handle_db_mode(cli: DbCommand, cfg: Cfg) {
-    let db = match Db::create_connection().await {
-        Ok(db) => db,
-        Err(err) => {
-            eprintln!("{}: {}", Colorize::red("Ruh roh, db isn't working"), err);
-            eprintln!("Exiting...");
-            std::process::exit(1)
-        }
-    };
+async fn handle_db_mode(cli: DbCommand, cfg: Cfg, db_path: &str) {
+    // the only one that doesn't need the db, which might not open without a key
+    if let DbCommand::Keygen = cli {
+        println!("{}", encryption::generate_key());
+        return;
+    }
+    if let DbCommand::Workspaces = cli {
+        print_workspaces(db_path);
+        return;
+    }
+
+    let mut db = open_db(db_path).await;
 
     match cli {
         DbCommand::ListAll(cli) => {
@@ -145,7 +338,7 @@ async fn handle_db_mode(cli: DbCommand, cfg: Cfg) {
                 std::process::exit(1)
             };
 
-            let list = match db.get_all_entries_by_time_range(date_time).await {
+            let list = match db.get_all_entries_by_time_range(date_time, &cli.tags).await {
                 Ok(list) => list,
                 Err(err) => {
                     eprintln!("Couldn't query all the entries: {}", err);
@@ -166,7 +359,7 @@ async fn handle_db_mode(cli: DbCommand, cfg: Cfg) {
             };
 
             let list = match db
-                .get_all_entries_by_service(cfg.get(&cli.service), date_time)
+                .get_all_entries_by_service(cfg.get(&cli.service), date_time, &cli.tags)
                 .await
             {
                 Ok(list) => list,
@@ -180,8 +373,302 @@ async fn handle_db_mode(cli: DbCommand, cfg: Cfg) {
                 println!("{}", entry)
             }
         }
-        DbCommand::Ui(_cli) => {
-            let _ = Ui::run(&db);
+        DbCommand::Show(cli) => {
+            let entry = match db.get_entry_by_id(cli.id).await {
+                Ok(Some(entry)) => entry,
+                Ok(None) => {
+                    eprintln!("No request stored with id {}", cli.id);
+                    std::process::exit(1)
+                }
+                Err(err) => {
+                    eprintln!("Couldn't query the entry: {}", err);
+                    std::process::exit(1)
+                }
+            };
+
+            match cli.select {
+                Some(selector) => {
+                    let response = entry
+                        .response_json()
+                        .cloned()
+                        .unwrap_or(serde_json::Value::Null);
+                    if let Err(err) = print_selected(&selector, &response) {
+                        eprintln!("Couldn't select from response: {}", err);
+                        std::process::exit(1)
+                    }
+                }
+                None => println!("{}", entry),
+            }
+        }
+        DbCommand::Delete(cli) => {
+            let target = cli.target.trim();
+            let mut filter = match target.trim_start_matches('#').parse::<i64>() {
+                Ok(id) => {
+                    match db.get_entry_by_id(id).await {
+                        Ok(Some(entry)) if entry.pinned() && !cli.include_pinned => {
+                            eprintln!(
+                                "#{} is pinned, unpin it or pass --include-pinned to delete it",
+                                id
+                            );
+                            std::process::exit(1)
+                        }
+                        Ok(Some(_)) => (),
+                        Ok(None) => {
+                            eprintln!("No request stored with id {}", id);
+                            std::process::exit(1)
+                        }
+                        Err(err) => {
+                            eprintln!("Couldn't query the entry: {}", err);
+                            std::process::exit(1)
+                        }
+                    }
+                    EntryFilter {
+                        id: Some(id),
+                        ..EntryFilter::default()
+                    }
+                }
+                Err(_) => match EntryFilter::parse(target) {
+                    Ok(filter) if filter.is_empty() => {
+                        eprintln!("The filter matches every request, narrow it down");
+                        std::process::exit(1)
+                    }
+                    Ok(filter) => filter,
+                    Err(err) => {
+                        eprintln!("Couldn't parse the filter: {}", err);
+                        std::process::exit(1)
+                    }
+                },
+            };
+            if !cli.include_pinned && filter.pinned.is_none() {
+                filter.pinned = Some(false);
+            }
+
+            let ids = match db.get_ids(&filter).await {
+                Ok(ids) => ids,
+                Err(err) => {
+                    eprintln!("Couldn't query the entries: {}", err);
+                    std::process::exit(1)
+                }
+            };
+            if ids.is_empty() {
+                println!("Nothing to delete");
+                return;
+            }
+
+            // a single id is explicit enough, filters can easily match more than intended
+            if filter.id.is_none() && !cli.yes && !confirm_delete(ids.len()) {
+                println!("Nothing deleted");
+                return;
+            }
+
+            match db.delete_entries(&ids).await {
+                Ok(deleted) => print_deleted(deleted),
+                Err(err) => {
+                    eprintln!("Couldn't delete: {}", err);
+                    std::process::exit(1)
+                }
+            }
+        }
+        DbCommand::Pin(cli) => set_pinned(&db, cli.id, true).await,
+        DbCommand::Unpin(cli) => set_pinned(&db, cli.id, false).await,
+        DbCommand::Note(cli) => {
+            let note = Some(cli.text.trim()).filter(|note| !note.is_empty());
+            match db.set_note(cli.id, note).await {
+                Ok(true) if note.is_some() => println!("Noted #{}", cli.id),
+                Ok(true) => println!("Removed the note from #{}", cli.id),
+                Ok(false) => {
+                    eprintln!("No request stored with id {}", cli.id);
+                    std::process::exit(1)
+                }
+                Err(err) => {
+                    eprintln!("Couldn't update the entry: {}", err);
+                    std::process::exit(1)
+                }
+            }
+        }
+        DbCommand::Tag(cli) => match db.add_tag(cli.id, &cli.tag).await {
+            Ok(true) => println!("Tagged #{} with {}", cli.id, cli.tag),
+            Ok(false) => {
+                eprintln!("No request stored with id {}", cli.id);
+                std::process::exit(1)
+            }
+            Err(err) => {
+                eprintln!("Couldn't tag the entry: {}", err);
+                std::process::exit(1)
+            }
+        },
+        DbCommand::Untag(cli) => match db.remove_tag(cli.id, &cli.tag).await {
+            Ok(true) => println!("Removed {} from #{}", cli.tag, cli.id),
+            Ok(false) => {
+                eprintln!("#{} isn't tagged {}", cli.id, cli.tag);
+                std::process::exit(1)
+            }
+            Err(err) => {
+                eprintln!("Couldn't untag the entry: {}", err);
+                std::process::exit(1)
+            }
+        },
+        DbCommand::Prune(cli) => {
+            let policy = match cli.older_than {
+                Some(max_age) => RetentionPolicy {
+                    max_age: Some(max_age),
+                    keep_pinned: cli.keep_pinned,
+                    ..RetentionPolicy::default()
+                },
+                None => {
+                    let mut policy = resolve_retention(&cfg);
+                    if policy.is_empty() {
+                        eprintln!(
+                            "Nothing to prune by, pass --older-than or set limits in [retention]"
+                        );
+                        std::process::exit(1)
+                    }
+                    policy.keep_pinned |= cli.keep_pinned;
+                    policy
+                }
+            };
+
+            let ids = match db.retention_ids(&policy).await {
+                Ok(ids) => ids,
+                Err(err) => {
+                    eprintln!("Couldn't query the entries: {}", err);
+                    std::process::exit(1)
+                }
+            };
+            if ids.is_empty() {
+                println!("Nothing to prune");
+                return;
+            }
+            if !cli.yes && !confirm_delete(ids.len()) {
+                println!("Nothing deleted");
+                return;
+            }
+
+            match db.delete_entries(&ids).await {
+                Ok(deleted) => print_deleted(deleted),
+                Err(err) => {
+                    eprintln!("Couldn't delete: {}", err);
+                    std::process::exit(1)
+                }
+            }
+        }
+        DbCommand::Stats => match db.stats().await {
+            Ok(stats) => pretty_print_db_stats(&stats),
+            Err(err) => {
+                eprintln!("Couldn't gather stats: {}", err);
+                std::process::exit(1)
+            }
+        },
+        DbCommand::Encrypt => match db.encrypt_existing().await {
+            Ok(1) => println!("Encrypted 1 request"),
+            Ok(count) => println!("Encrypted {} requests", count),
+            Err(err) => {
+                eprintln!("Couldn't encrypt: {}", err);
+                std::process::exit(1)
+            }
+        },
+        DbCommand::Keygen | DbCommand::Workspaces => (),
+        DbCommand::Export(cli) => {
+            let mut out: Box<dyn std::io::Write> = match &cli.output {
+                Some(path) => match std::fs::File::create(path) {
+                    Ok(file) => Box::new(std::io::BufWriter::new(file)),
+                    Err(err) => {
+                        eprintln!("Couldn't create {}: {}", path, err);
+                        std::process::exit(1)
+                    }
+                },
+                None => Box::new(std::io::BufWriter::new(std::io::stdout().lock())),
+            };
+            match export::export(&db, cli.format, &mut out).await {
+                // stdout might be the export itself
+                Ok(summary) => {
+                    eprintln!("Exported {} requests", summary.exported);
+                    if summary.duplicates > 0 {
+                        eprintln!(
+                            "Left out {} exact duplicates of exported requests",
+                            summary.duplicates
+                        );
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Couldn't export: {}", err);
+                    std::process::exit(1)
+                }
+            }
+        }
+        DbCommand::Import(cli) => {
+            let mut input: Box<dyn std::io::BufRead> = if cli.file == "-" {
+                Box::new(std::io::stdin().lock())
+            } else {
+                match std::fs::File::open(&cli.file) {
+                    Ok(file) => Box::new(std::io::BufReader::new(file)),
+                    Err(err) => {
+                        eprintln!("Couldn't open {}: {}", cli.file, err);
+                        std::process::exit(1)
+                    }
+                }
+            };
+            match export::import(&mut db, &mut input).await {
+                Ok(summary) => println!(
+                    "Imported {} requests, {} were already stored",
+                    summary.imported, summary.skipped
+                ),
+                Err(err) => {
+                    eprintln!("Couldn't import {}: {}", cli.file, err);
+                    std::process::exit(1)
+                }
+            }
+        }
+        DbCommand::Sync => {
+            let Some(remote) = remote::Remote::from_config(&cfg) else {
+                eprintln!("Nothing to sync with, set url in [remote]");
+                std::process::exit(1)
+            };
+            match remote.sync(&db).await {
+                Ok(summary) => {
+                    println!(
+                        "Pushed {} and pulled {} requests ({})",
+                        summary.pushed, summary.pulled, remote.url
+                    );
+                    if summary.tags_pushed > 0 || summary.tags_pulled > 0 {
+                        println!(
+                            "Pushed {} and pulled {} tags",
+                            summary.tags_pushed, summary.tags_pulled
+                        );
+                    }
+                    if summary.skipped_encrypted > 0 {
+                        eprintln!(
+                            "Skipped {} encrypted requests, they only sync with a shared SCOOBY_DB_KEY or key_file",
+                            summary.skipped_encrypted
+                        );
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Couldn't sync with {}: {}", remote.url, err);
+                    std::process::exit(1)
+                }
+            }
+        }
+        DbCommand::Ui(cli) => {
+            // the stored service is the resolved name rather than the config key, so the
+            // composer goes with the global http/retry settings
+            let http_settings = HttpSettings::resolve(&cfg, "", &cli.http);
+            // browsing works without a token, only sending needs one
+            let http_client = std::env::var("auth_token")
+                .is_ok()
+                .then(|| create_http_client(&http_settings));
+            let retry_policy = RetryPolicy::resolve(&cfg, "", None);
+            let retention = resolve_retention(&cfg);
+            let ui_config: UiConfig = cfg.get_section("ui").unwrap_or_default();
+
+            let _ = Ui::run(
+                db,
+                http_client,
+                retry_policy,
+                retention,
+                cli.follow,
+                ui_config,
+            );
         }
     };
 }
@@ -190,14 +677,31 @@ async fn handle_db_mode(cli: DbCommand, cfg: Cfg) {
 async fn main() -> Result<(), reqwest::Error> {
     let args = ScoobyArgs::parse();
 
+    // plain output when piped, colored_json already does this on its own
+    if !std::io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
+
     let cfg = Cfg::parse_from_file();
+    redact::init(&cfg);
+    encryption::init(&cfg);
+    let db_path = match workspace::resolve_db_path(args.db, args.workspace, &cfg) {
+        Ok(db_path) => db_path,
+        Err(err) => {
+            eprintln!("Couldn't figure out where the db is: {}", err);
+            std::process::exit(1)
+        }
+    };
 
     match args.mode_type {
         ModeType::Req(cli) => {
-            handle_req_mode(cli, cfg).await;
+            handle_req_mode(cli, cfg, &db_path).await;
         }
         ModeType::Db(cli) => {
-            handle_db_mode(cli, cfg).await;
+            handle_db_mode(cli, cfg, &db_path).await;
+        }
+        ModeType::Bench(cli) => {
+            handle_bench_mode(cli, cfg, &db_path).await;
         }
     }
 
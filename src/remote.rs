@@ -0,0 +1,388 @@
+use base64::{Engine, engine::general_purpose::STANDARD_NO_PAD};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::error::Error;
+
+use crate::cfg::Cfg;
+use crate::db::{Db, STORED_COLUMNS, StoredRequest, insert_stored_sql};
+use crate::encryption;
+
+const TOKEN_ENV: &str = "SCOOBY_REMOTE_TOKEN";
+/// Rows sent or fetched per round trip.
+const SYNC_PAGE_SIZE: i64 = 200;
+
+/// `[remote]` in the config file.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RemoteConfig {
+    /// `libsql://<db>.turso.io`, or `http://127.0.0.1:8080` for a local `sqld`
+    pub url: Option<String>,
+    /// `SCOOBY_REMOTE_TOKEN` wins over this
+    pub auth_token: Option<String>,
+}
+
+/// Same columns as the local table, minus the local bits, so `turso db shell` queries work on
+/// both.
+const REMOTE_TABLE: &str = "CREATE TABLE IF NOT EXISTS requests (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        content_hash TEXT NOT NULL UNIQUE,
+        method TEXT NOT NULL,
+        service TEXT NOT NULL,
+        route_url TEXT NOT NULL,
+        full_url TEXT NOT NULL,
+        payload TEXT,
+        response_json TEXT,
+        created_at TEXT NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 1,
+        status INTEGER,
+        server_env TEXT,
+        qsp TEXT,
+        request_headers TEXT,
+        response_headers TEXT,
+        elapsed_ms INTEGER,
+        pinned INTEGER NOT NULL DEFAULT 0,
+        note TEXT,
+        user TEXT,
+        host TEXT)";
+
+/// Tags by content hash, the ids differ between the remote and every local db.
+const REMOTE_TAGS_TABLE: &str = "CREATE TABLE IF NOT EXISTS request_tags (
+        content_hash TEXT NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (content_hash, tag))";
+
+/// Only tags requests that made it to the remote, a skipped encrypted one keeps its tags local.
+const PUSH_TAG_SQL: &str = "INSERT INTO request_tags (content_hash, tag)
+        SELECT ?1, ?2 WHERE EXISTS (SELECT 1 FROM requests WHERE content_hash = ?1)
+        ON CONFLICT DO NOTHING";
+
+const PULL_TAGS_SQL: &str = "SELECT rowid, content_hash, tag FROM request_tags
+        WHERE rowid > ? ORDER BY rowid ASC LIMIT ?";
+
+/// A libSQL server spoken to over its HTTP api (hrana over http), which both Turso and `sqld`
+/// serve.
+pub struct Remote {
+    client: reqwest::Client,
+    /// what's in the config, used to keep track of how far syncing got
+    pub url: String,
+    pipeline_url: String,
+    auth_token: Option<String>,
+}
+
+/// What `db sync` did.
+pub struct SyncSummary {
+    pub pushed: u64,
+    pub pulled: u64,
+    /// encrypted requests left where they were, there's no shared key to read them with
+    pub skipped_encrypted: u64,
+    pub tags_pushed: u64,
+    pub tags_pulled: u64,
+}
+
+struct QueryResult {
+    rows: Vec<Vec<turso::Value>>,
+    affected: u64,
+}
+
+fn to_hrana(value: turso::Value) -> Value {
+    match value {
+        turso::Value::Null => json!({ "type": "null" }),
+        turso::Value::Integer(int) => json!({ "type": "integer", "value": int.to_string() }),
+        turso::Value::Real(real) => json!({ "type": "float", "value": real }),
+        turso::Value::Text(text) => json!({ "type": "text", "value": text }),
+        turso::Value::Blob(blob) => {
+            json!({ "type": "blob", "base64": STANDARD_NO_PAD.encode(blob) })
+        }
+    }
+}
+
+fn from_hrana(value: &Value) -> Result<turso::Value, String> {
+    let invalid = || format!("unexpected value from the remote: {value}");
+    let raw = &value["value"];
+    match value["type"].as_str() {
+        Some("null") => Ok(turso::Value::Null),
+        Some("integer") => raw
+            .as_str()
+            .and_then(|int| int.parse().ok())
+            .map(turso::Value::Integer)
+            .ok_or_else(invalid),
+        Some("float") => raw.as_f64().map(turso::Value::Real).ok_or_else(invalid),
+        Some("text") => raw
+            .as_str()
+            .map(|text| turso::Value::Text(text.to_string()))
+            .ok_or_else(invalid),
+        Some("blob") => value["base64"]
+            .as_str()
+            .and_then(|blob| STANDARD_NO_PAD.decode(blob.trim_end_matches('=')).ok())
+            .map(turso::Value::Blob)
+            .ok_or_else(invalid),
+        _ => Err(invalid()),
+    }
+}
+
+impl Remote {
+    /// `None` when there's no `[remote]` url.
+    pub fn from_config(cfg: &Cfg) -> Option<Remote> {
+        let config: RemoteConfig = cfg.get_section("remote").unwrap_or_default();
+        let url = config.url?;
+        let base = match url.strip_prefix("libsql://") {
+            Some(host) => format!("https://{host}"),
+            None => url.clone(),
+        };
+        let auth_token = std::env::var(TOKEN_ENV)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .or(config.auth_token);
+
+        Some(Remote {
+            client: reqwest::Client::new(),
+            pipeline_url: format!("{}/v2/pipeline", base.trim_end_matches('/')),
+            url,
+            auth_token,
+        })
+    }
+
+    /// Runs the statements in one round trip, in order. Not a transaction, a failure halfway
+    /// leaves the ones before it applied.
+    async fn execute(
+        &self,
+        statements: Vec<(String, Vec<turso::Value>)>,
+    ) -> Result<Vec<QueryResult>, Box<dyn Error>> {
+        let mut requests: Vec<Value> = statements
+            .into_iter()
+            .map(|(sql, args)| {
+                let args: Vec<Value> = args.into_iter().map(to_hrana).collect();
+                json!({ "type": "execute", "stmt": { "sql": sql, "args": args } })
+            })
+            .collect();
+        requests.push(json!({ "type": "close" }));
+
+        let mut request = self
+            .client
+            .post(&self.pipeline_url)
+            .json(&json!({ "baton": null, "requests": requests }));
+        if let Some(token) = &self.auth_token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(format!("answered {}: {}", status, body.trim()).into());
+        }
+
+        let body: Value = response.json().await?;
+        let results = body["results"]
+            .as_array()
+            .ok_or_else(|| format!("unexpected answer: {}", body))?;
+        let mut output = Vec::new();
+        // the last one is the close
+        for result in results.iter().take(results.len().saturating_sub(1)) {
+            if result["type"] == "error" {
+                let message = result["error"]["message"]
+                    .as_str()
+                    .unwrap_or("unknown error");
+                return Err(format!("query failed: {}", message).into());
+            }
+            let result = &result["response"]["result"];
+            let rows = result["rows"]
+                .as_array()
+                .map(Vec::as_slice)
+                .unwrap_or_default()
+                .iter()
+                .map(|row| {
+                    row.as_array()
+                        .map(Vec::as_slice)
+                        .unwrap_or_default()
+                        .iter()
+                        .map(from_hrana)
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<_, _>>()?;
+            output.push(QueryResult {
+                rows,
+                affected: result["affected_row_count"].as_u64().unwrap_or(0),
+            });
+        }
+        Ok(output)
+    }
+
+    /// Pushes what's new locally, then pulls what others pushed since the last sync. Requests are
+    /// matched by content hash, so syncing again or from two places never duplicates anything.
+    /// Pins stay local. Encrypted requests only move with a raw key, see
+    /// `encryption::has_shared_key`, anything else couldn't be decrypted on the other side.
+    pub async fn sync(&self, db: &Db) -> Result<SyncSummary, Box<dyn Error>> {
+        self.execute(vec![
+            (REMOTE_TABLE.to_string(), Vec::new()),
+            (REMOTE_TAGS_TABLE.to_string(), Vec::new()),
+        ])
+        .await?;
+        db.fill_content_hashes().await?;
+
+        let pushed_key = format!("{} pushed", self.url);
+        let pulled_key = format!("{} pulled", self.url);
+        let shared_key = encryption::has_shared_key();
+        let mut summary = SyncSummary {
+            pushed: 0,
+            pulled: 0,
+            skipped_encrypted: 0,
+            tags_pushed: 0,
+            tags_pulled: 0,
+        };
+
+        let insert_sql = format!(
+            "{} ON CONFLICT (content_hash) DO NOTHING",
+            insert_stored_sql()
+        );
+        // the markers never move past a skipped encrypted row, so it's tried again once there's
+        // a shared key, everything after it is sent again and ignored as a duplicate meanwhile
+        let mut pushed_id = db.get_sync_state(&pushed_key).await?;
+        let mut after_id = pushed_id;
+        let mut held = false;
+        loop {
+            let page = db.get_stored_since(after_id, SYNC_PAGE_SIZE).await?;
+            let Some((last_id, _)) = page.last() else {
+                break;
+            };
+            after_id = *last_id;
+            let mut statements = Vec::new();
+            let mut sent_upto = pushed_id;
+            for (id, mut stored) in page {
+                if stored.is_encrypted() && !shared_key {
+                    summary.skipped_encrypted += 1;
+                    held = true;
+                    continue;
+                }
+                if !held {
+                    sent_upto = id;
+                }
+                stored.pinned = false;
+                statements.push((insert_sql.clone(), stored.params()));
+            }
+            for result in self.execute(statements).await? {
+                summary.pushed += result.affected;
+            }
+            if sent_upto != pushed_id {
+                pushed_id = sent_upto;
+                db.set_sync_state(&pushed_key, pushed_id).await?;
+            }
+        }
+
+        let select_sql = format!(
+            "SELECT id, {} FROM requests WHERE id > ? ORDER BY id ASC LIMIT ?",
+            STORED_COLUMNS
+        );
+        let mut pulled_id = db.get_sync_state(&pulled_key).await?;
+        let mut after_id = pulled_id;
+        let mut held = false;
+        let mut pulled_ids = HashSet::new();
+        loop {
+            let params = vec![
+                turso::Value::Integer(after_id),
+                turso::Value::Integer(SYNC_PAGE_SIZE),
+            ];
+            let rows = self
+                .execute(vec![(select_sql.clone(), params)])
+                .await?
+                .pop()
+                .map(|result| result.rows)
+                .unwrap_or_default();
+            if rows.is_empty() {
+                break;
+            }
+            for mut row in rows {
+                let id = match row.first() {
+                    Some(turso::Value::Integer(id)) => *id,
+                    _ => return Err("got a row without an id".into()),
+                };
+                after_id = id;
+                let stored = StoredRequest::from_values(row.split_off(1))?;
+                if stored.is_encrypted() && !shared_key {
+                    summary.skipped_encrypted += 1;
+                    held = true;
+                    continue;
+                }
+                if let Some(local_id) = db.insert_stored(&stored).await? {
+                    pulled_ids.insert(local_id);
+                    summary.pulled += 1;
+                }
+                if !held {
+                    pulled_id = id;
+                }
+            }
+            db.set_sync_state(&pulled_key, pulled_id).await?;
+        }
+
+        // what was just pulled is on the remote already, no need to push it back next time. Only
+        // rows right after the marker though, anything stored meanwhile still has to go out.
+        'pulled: while !pulled_ids.is_empty() {
+            let page = db.get_stored_since(pushed_id, SYNC_PAGE_SIZE).await?;
+            if page.is_empty() {
+                break;
+            }
+            for (id, _) in page {
+                if !pulled_ids.remove(&id) {
+                    break 'pulled;
+                }
+                pushed_id = id;
+            }
+        }
+        db.set_sync_state(&pushed_key, pushed_id).await?;
+
+        self.sync_tags(db, &mut summary).await?;
+        Ok(summary)
+    }
+
+    /// All tags both ways on every sync, there are few of them and they're added to requests
+    /// that synced long ago. Removing a tag stays local.
+    async fn sync_tags(&self, db: &Db, summary: &mut SyncSummary) -> Result<(), Box<dyn Error>> {
+        let tags = db.get_hashed_tags().await?;
+        for chunk in tags.chunks(SYNC_PAGE_SIZE as usize) {
+            let statements = chunk
+                .iter()
+                .map(|(content_hash, tag)| {
+                    let params = vec![
+                        turso::Value::Text(content_hash.clone()),
+                        turso::Value::Text(tag.clone()),
+                    ];
+                    (PUSH_TAG_SQL.to_string(), params)
+                })
+                .collect();
+            for result in self.execute(statements).await? {
+                summary.tags_pushed += result.affected;
+            }
+        }
+
+        let mut after_rowid = 0;
+        loop {
+            let params = vec![
+                turso::Value::Integer(after_rowid),
+                turso::Value::Integer(SYNC_PAGE_SIZE),
+            ];
+            let rows = self
+                .execute(vec![(PULL_TAGS_SQL.to_string(), params)])
+                .await?
+                .pop()
+                .map(|result| result.rows)
+                .unwrap_or_default();
+            if rows.is_empty() {
+                break;
+            }
+            for row in rows {
+                let (rowid, content_hash, tag) = match row.as_slice() {
+                    [
+                        turso::Value::Integer(rowid),
+                        turso::Value::Text(content_hash),
+                        turso::Value::Text(tag),
+                    ] => (*rowid, content_hash, tag),
+                    _ => return Err("got a tag row that doesn't look like one".into()),
+                };
+                after_rowid = rowid;
+                if db.add_tag_by_hash(content_hash, tag).await? {
+                    summary.tags_pulled += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+}
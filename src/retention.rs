@@ -0,0 +1,63 @@
+use chrono::Duration;
+use serde::Deserialize;
+
+use crate::cfg::Cfg;
+
+/// `[retention]` in the config file. Nothing is pruned automatically unless a limit is set.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RetentionConfig {
+    /// e.g. `30d`, `12h` or `2w`
+    pub max_age: Option<String>,
+    pub max_rows: Option<u64>,
+    /// payloads and responses together
+    pub max_body_bytes: Option<u64>,
+    /// defaults to true, pinned requests don't count towards the limits either
+    pub keep_pinned: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    /// newest ones are kept
+    pub max_rows: Option<u64>,
+    /// newest ones are kept until their bodies add up to this
+    pub max_body_bytes: Option<u64>,
+    pub keep_pinned: bool,
+}
+
+/// `30d`, `12h`, `90m`, `45s` or `2w`.
+pub fn parse_age(s: &str) -> Result<Duration, String> {
+    let s = s.trim();
+    let invalid = || format!("invalid age `{s}`, use something like 30d, 12h or 2w");
+    let unit_at = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (amount, unit) = s.split_at(unit_at);
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let age = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => None,
+    };
+    age.ok_or_else(invalid)
+}
+
+impl RetentionPolicy {
+    /// What gets pruned after every stored request, from `[retention]`.
+    pub fn resolve(cfg: &Cfg) -> Result<Self, String> {
+        let config: RetentionConfig = cfg.get_section("retention").unwrap_or_default();
+
+        Ok(RetentionPolicy {
+            max_age: config.max_age.as_deref().map(parse_age).transpose()?,
+            max_rows: config.max_rows,
+            max_body_bytes: config.max_body_bytes,
+            keep_pinned: config.keep_pinned.unwrap_or(true),
+        })
+    }
+
+    /// No limits, nothing would ever be pruned.
+    pub fn is_empty(&self) -> bool {
+        self.max_age.is_none() && self.max_rows.is_none() && self.max_body_bytes.is_none()
+    }
+}
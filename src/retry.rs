@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use serde::Deserialize;
+
+use crate::cfg::Cfg;
+
+/// `[retry]` in the config file, also used for `[services.<service>.retry]` overrides.
+/// Every field is optional so that a service override only needs to list what it changes.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RetryConfig {
+    pub count: Option<u32>,
+    pub base_delay_ms: Option<u64>,
+    pub max_delay_ms: Option<u64>,
+    pub statuses: Option<Vec<u16>>,
+    pub on_timeout: Option<bool>,
+    pub on_connect: Option<bool>,
+}
+
+impl RetryConfig {
+    fn merge(self, other: RetryConfig) -> RetryConfig {
+        RetryConfig {
+            count: other.count.or(self.count),
+            base_delay_ms: other.base_delay_ms.or(self.base_delay_ms),
+            max_delay_ms: other.max_delay_ms.or(self.max_delay_ms),
+            statuses: other.statuses.or(self.statuses),
+            on_timeout: other.on_timeout.or(self.on_timeout),
+            on_connect: other.on_connect.or(self.on_connect),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// retries on top of the first attempt, 0 means a single attempt
+    pub count: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub statuses: Vec<u16>,
+    pub on_timeout: bool,
+    pub on_connect: bool,
+}
+
+impl RetryPolicy {
+    /// Resolves the policy for a service: defaults, then `[retry]`, then
+    /// `[services.<service>.retry]`, then the `--retry` count given on the command line.
+    pub fn resolve(cfg: &Cfg, service: &str, cli_count: Option<u32>) -> Self {
+        let global: RetryConfig = cfg.get_section("retry").unwrap_or_default();
+        let per_service: RetryConfig = cfg
+            .get_service_section(service, "retry")
+            .unwrap_or_default();
+        let merged = global.merge(per_service);
+
+        RetryPolicy {
+            count: cli_count.or(merged.count).unwrap_or(0),
+            base_delay: Duration::from_millis(merged.base_delay_ms.unwrap_or(250)),
+            max_delay: Duration::from_millis(merged.max_delay_ms.unwrap_or(10_000)),
+            // the usual suspects when dev stacks are cold starting
+            statuses: merged.statuses.unwrap_or_else(|| vec![502, 503, 504]),
+            on_timeout: merged.on_timeout.unwrap_or(true),
+            on_connect: merged.on_connect.unwrap_or(true),
+        }
+    }
+
+    pub fn is_retryable_status(&self, status: u16) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    pub fn is_retryable_error(&self, err: &reqwest::Error) -> bool {
+        (self.on_timeout && err.is_timeout()) || (self.on_connect && err.is_connect())
+    }
+
+    /// Exponential backoff with full jitter, `retry` starts from 0 for the first retry.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_delay);
+        let millis = exp.as_millis() as u64;
+        Duration::from_millis(fastrand::u64(0..=millis))
+    }
+
+    /// Delay asked for by the server, capped so a silly header can't hang us forever.
+    pub fn retry_after(&self, headers: &HeaderMap) -> Option<Duration> {
+        let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+
+        let delay = if let Ok(secs) = value.parse::<u64>() {
+            Duration::from_secs(secs)
+        } else {
+            let at = DateTime::parse_from_rfc2822(value)
+                .ok()?
+                .with_timezone(&Utc);
+            (at - Utc::now()).to_std().unwrap_or_default()
+        };
+
+        Some(delay.min(self.max_delay))
+    }
+}
+
+/// A single try of a request, stored in history next to the request itself.
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    pub number: u32,
+    pub status: Option<u16>,
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+    /// how long we waited before the next attempt, 0 for the last one
+    pub delay_ms: u64,
+}
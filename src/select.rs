@@ -0,0 +1,646 @@
+use std::{cmp::Ordering, iter::Peekable, str::Chars, str::FromStr};
+
+use serde_json::Value;
+
+/// A jq/JSONPath-ish expression evaluated over a json value.
+///
+/// Supported: `.field`, `."quoted field"`, `.["field"]`, `.[0]`, `.[-1]`, wildcards `.[]`,
+/// `.[*]` and `.*`, filters `.[?(.field == "x")]` (`@` works as the current element too,
+/// operators `== != < <= > >=`, or just `.[?(.field)]` for truthiness), a leading `$` like
+/// JSONPath, and piping into `length` or `keys`: `.items | length`.
+#[derive(Debug, Clone)]
+pub struct Selector {
+    stages: Vec<Stage>,
+}
+
+#[derive(Debug, Clone)]
+enum Stage {
+    Path(Vec<Step>),
+    Length,
+    Keys,
+}
+
+#[derive(Debug, Clone)]
+enum Step {
+    Field(String),
+    Index(i64),
+    Wildcard,
+    Filter(Box<Filter>),
+}
+
+#[derive(Debug, Clone)]
+struct Filter {
+    path: Vec<Step>,
+    cmp: Option<(CmpOp, Value)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl FromStr for Selector {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parser = Parser {
+            chars: s.chars().peekable(),
+        };
+        let stages = parser.parse_stages()?;
+        Ok(Selector { stages })
+    }
+}
+
+impl Selector {
+    pub fn eval(&self, input: &Value) -> Result<Vec<Value>, String> {
+        let mut values = vec![input.clone()];
+
+        for stage in self.stages.iter() {
+            values = match stage {
+                Stage::Path(steps) => apply_steps(values, steps)?,
+                Stage::Length => values.iter().map(length).collect::<Result<_, _>>()?,
+                Stage::Keys => values.iter().map(keys).collect::<Result<_, _>>()?,
+            };
+        }
+
+        Ok(values)
+    }
+
+    /// Runs `f` on every value the path points at, in place. Missing fields and indexes are
+    /// skipped. Only plain paths work, `length` and `keys` make up new values.
+    pub fn for_each_mut(
+        &self,
+        input: &mut Value,
+        f: &mut dyn FnMut(&mut Value),
+    ) -> Result<(), String> {
+        match self.stages.as_slice() {
+            [] => f(input),
+            [Stage::Path(steps)] => visit_mut(input, steps, f),
+            _ => return Err("only plain paths work here, no `| length` or `| keys`".into()),
+        }
+        Ok(())
+    }
+}
+
+fn visit_mut(value: &mut Value, steps: &[Step], f: &mut dyn FnMut(&mut Value)) {
+    let Some((step, rest)) = steps.split_first() else {
+        return f(value);
+    };
+    match (step, value) {
+        (Step::Field(name), Value::Object(map)) => {
+            if let Some(value) = map.get_mut(name) {
+                visit_mut(value, rest, f);
+            }
+        }
+        (Step::Index(idx), Value::Array(items)) => {
+            let len = items.len() as i64;
+            let idx = if *idx < 0 { len + idx } else { *idx };
+            if (0..len).contains(&idx) {
+                visit_mut(&mut items[idx as usize], rest, f);
+            }
+        }
+        (Step::Wildcard, Value::Array(items)) => {
+            for item in items {
+                visit_mut(item, rest, f);
+            }
+        }
+        (Step::Wildcard, Value::Object(map)) => {
+            for item in map.values_mut() {
+                visit_mut(item, rest, f);
+            }
+        }
+        (Step::Filter(filter), Value::Array(items)) => {
+            for item in items {
+                if matches_filter(item, filter) {
+                    visit_mut(item, rest, f);
+                }
+            }
+        }
+        (Step::Filter(filter), Value::Object(map)) => {
+            for item in map.values_mut() {
+                if matches_filter(item, filter) {
+                    visit_mut(item, rest, f);
+                }
+            }
+        }
+        // nothing there
+        _ => (),
+    }
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_ws();
+        if self.chars.peek() == Some(&expected) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.eat(expected) {
+            Ok(())
+        } else {
+            Err(match self.chars.peek() {
+                Some(found) => format!("expected `{expected}`, found `{found}`"),
+                None => format!("expected `{expected}`, found end of expression"),
+            })
+        }
+    }
+
+    fn parse_stages(&mut self) -> Result<Vec<Stage>, String> {
+        let mut stages = Vec::new();
+
+        loop {
+            self.skip_ws();
+            let stage = match self.chars.peek() {
+                Some('.') | Some('$') | Some('@') | Some('[') => Stage::Path(self.parse_path()?),
+                Some(c) if c.is_alphabetic() => match self.parse_ident().as_str() {
+                    "length" => Stage::Length,
+                    "keys" => Stage::Keys,
+                    other => return Err(format!("unknown function `{other}`")),
+                },
+                Some(c) => return Err(format!("unexpected `{c}`")),
+                None => return Err("empty expression".into()),
+            };
+            stages.push(stage);
+
+            self.skip_ws();
+            match self.chars.next() {
+                Some('|') => continue,
+                Some(c) => return Err(format!("unexpected `{c}` after expression")),
+                None => return Ok(stages),
+            }
+        }
+    }
+
+    fn parse_ident(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '_' || c == '-' {
+                ident.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        ident
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        let quote = match self.chars.next() {
+            Some(c @ ('"' | '\'')) => c,
+            _ => return Err("expected a quoted string".into()),
+        };
+
+        let mut out = String::new();
+        loop {
+            match self.chars.next() {
+                Some('\\') => match self.chars.next() {
+                    Some(c) => out.push(c),
+                    None => return Err("unterminated string".into()),
+                },
+                Some(c) if c == quote => return Ok(out),
+                Some(c) => out.push(c),
+                None => return Err("unterminated string".into()),
+            }
+        }
+    }
+
+    fn parse_path(&mut self) -> Result<Vec<Step>, String> {
+        let mut steps = Vec::new();
+        self.skip_ws();
+        if matches!(self.chars.peek(), Some('$') | Some('@')) {
+            self.chars.next();
+        }
+
+        loop {
+            match self.chars.peek() {
+                Some('.') => {
+                    self.chars.next();
+                    match self.chars.peek() {
+                        Some('*') => {
+                            self.chars.next();
+                            steps.push(Step::Wildcard);
+                        }
+                        Some('"') | Some('\'') => steps.push(Step::Field(self.parse_string()?)),
+                        Some(c) if c.is_alphanumeric() || *c == '_' => {
+                            steps.push(Step::Field(self.parse_ident()))
+                        }
+                        // `.` on its own, or `.[` which is handled on the next round
+                        _ => {}
+                    }
+                }
+                Some('[') => {
+                    self.chars.next();
+                    steps.push(self.parse_bracket()?);
+                }
+                _ => return Ok(steps),
+            }
+        }
+    }
+
+    fn parse_bracket(&mut self) -> Result<Step, String> {
+        self.skip_ws();
+        let step = match self.chars.peek() {
+            Some(']') => Step::Wildcard,
+            Some('*') => {
+                self.chars.next();
+                Step::Wildcard
+            }
+            Some('"') | Some('\'') => Step::Field(self.parse_string()?),
+            Some('?') => {
+                self.chars.next();
+                let parens = self.eat('(');
+                let filter = self.parse_filter()?;
+                if parens {
+                    self.expect(')')?;
+                }
+                Step::Filter(Box::new(filter))
+            }
+            Some(_) => {
+                let mut number = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_ascii_digit() || c == '-' {
+                        number.push(c);
+                        self.chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let idx = number
+                    .parse()
+                    .map_err(|_| format!("invalid array index `{number}`"))?;
+                Step::Index(idx)
+            }
+            None => return Err("unterminated `[`".into()),
+        };
+        self.expect(']')?;
+        Ok(step)
+    }
+
+    fn parse_filter(&mut self) -> Result<Filter, String> {
+        self.skip_ws();
+        let path = self.parse_path()?;
+        self.skip_ws();
+
+        let mut op = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if matches!(c, '=' | '!' | '<' | '>') {
+                op.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+
+        let op = match op.as_str() {
+            "" => return Ok(Filter { path, cmp: None }),
+            "==" => CmpOp::Eq,
+            "!=" => CmpOp::Ne,
+            "<" => CmpOp::Lt,
+            "<=" => CmpOp::Le,
+            ">" => CmpOp::Gt,
+            ">=" => CmpOp::Ge,
+            other => return Err(format!("unknown operator `{other}`")),
+        };
+
+        let literal = self.parse_literal()?;
+        Ok(Filter {
+            path,
+            cmp: Some((op, literal)),
+        })
+    }
+
+    fn parse_literal(&mut self) -> Result<Value, String> {
+        self.skip_ws();
+        match self.chars.peek() {
+            Some('"') | Some('\'') => Ok(Value::String(self.parse_string()?)),
+            Some(_) => {
+                let mut raw = String::new();
+                while let Some(&c) = self.chars.peek() {
+                    if c.is_alphanumeric() || matches!(c, '-' | '+' | '.') {
+                        raw.push(c);
+                        self.chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                match raw.as_str() {
+                    "true" => Ok(Value::Bool(true)),
+                    "false" => Ok(Value::Bool(false)),
+                    "null" => Ok(Value::Null),
+                    number => serde_json::from_str::<serde_json::Number>(number)
+                        .map(Value::Number)
+                        .map_err(|_| format!("invalid literal `{number}`")),
+                }
+            }
+            None => Err("expected a value to compare against".into()),
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn apply_steps(mut values: Vec<Value>, steps: &[Step]) -> Result<Vec<Value>, String> {
+    for step in steps {
+        let mut next = Vec::new();
+        for value in values {
+            apply_step(value, step, &mut next)?;
+        }
+        values = next;
+    }
+    Ok(values)
+}
+
+fn apply_step(value: Value, step: &Step, out: &mut Vec<Value>) -> Result<(), String> {
+    match (step, value) {
+        (Step::Field(name), Value::Object(mut map)) => {
+            out.push(map.remove(name).unwrap_or(Value::Null))
+        }
+        (Step::Index(idx), Value::Array(mut items)) => {
+            let len = items.len() as i64;
+            let idx = if *idx < 0 { len + idx } else { *idx };
+            if (0..len).contains(&idx) {
+                out.push(items.swap_remove(idx as usize));
+            } else {
+                out.push(Value::Null);
+            }
+        }
+        (Step::Field(_) | Step::Index(_), Value::Null) => out.push(Value::Null),
+        (Step::Wildcard, Value::Array(items)) => out.extend(items),
+        (Step::Wildcard, Value::Object(map)) => out.extend(map.into_iter().map(|(_, v)| v)),
+        (Step::Filter(filter), Value::Array(items)) => out.extend(
+            items
+                .into_iter()
+                .filter(|item| matches_filter(item, filter)),
+        ),
+        (Step::Filter(filter), Value::Object(map)) => out.extend(
+            map.into_iter()
+                .map(|(_, v)| v)
+                .filter(|item| matches_filter(item, filter)),
+        ),
+        (Step::Field(name), other) => {
+            return Err(format!(
+                "cannot index {} with \"{}\"",
+                type_name(&other),
+                name
+            ));
+        }
+        (Step::Index(idx), other) => {
+            return Err(format!("cannot index {} with {}", type_name(&other), idx));
+        }
+        (Step::Wildcard | Step::Filter(_), other) => {
+            return Err(format!("cannot iterate over {}", type_name(&other)));
+        }
+    }
+    Ok(())
+}
+
+fn matches_filter(item: &Value, filter: &Filter) -> bool {
+    let found = match apply_steps(vec![item.clone()], &filter.path) {
+        Ok(mut values) if !values.is_empty() => values.remove(0),
+        _ => return false,
+    };
+
+    match &filter.cmp {
+        None => !matches!(found, Value::Null | Value::Bool(false)),
+        Some((op, literal)) => {
+            let ordering = compare(&found, literal);
+            match op {
+                CmpOp::Eq => ordering == Some(Ordering::Equal),
+                CmpOp::Ne => ordering != Some(Ordering::Equal),
+                CmpOp::Lt => ordering == Some(Ordering::Less),
+                CmpOp::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+                CmpOp::Gt => ordering == Some(Ordering::Greater),
+                CmpOp::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            }
+        }
+    }
+}
+
+/// Numbers compare numerically and strings lexically, anything else only by equality.
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => a.as_f64()?.partial_cmp(&b.as_f64()?),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (a, b) if a == b => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+fn length(value: &Value) -> Result<Value, String> {
+    let len = match value {
+        Value::Null => 0,
+        Value::String(s) => s.chars().count(),
+        Value::Array(items) => items.len(),
+        Value::Object(map) => map.len(),
+        Value::Number(n) => return Ok(n.as_f64().map(f64::abs).into()),
+        Value::Bool(_) => return Err("boolean has no length".into()),
+    };
+    Ok(len.into())
+}
+
+fn keys(value: &Value) -> Result<Value, String> {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            Ok(keys.into_iter().cloned().map(Value::String).collect())
+        }
+        Value::Array(items) => Ok((0..items.len()).map(Value::from).collect()),
+        other => Err(format!("{} has no keys", type_name(other))),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn select(expr: &str, input: &Value) -> Result<Vec<Value>, String> {
+        expr.parse::<Selector>()?.eval(input)
+    }
+
+    fn sample() -> Value {
+        json!({
+            "items": [
+                {"id": 1, "name": "a", "active": true},
+                {"id": 2, "name": "b", "active": false},
+                {"id": 3, "name": "c"}
+            ],
+            "by name": {"x": {"id": 10}, "y": {"id": 20}},
+            "token": "secret"
+        })
+    }
+
+    #[test]
+    fn parses_field_forms() {
+        let input = sample();
+        assert_eq!(select(".token", &input).unwrap(), vec![json!("secret")]);
+        assert_eq!(select("$.token", &input).unwrap(), vec![json!("secret")]);
+        assert_eq!(
+            select(".[\"token\"]", &input).unwrap(),
+            vec![json!("secret")]
+        );
+        assert_eq!(
+            select(".\"by name\".x.id", &input).unwrap(),
+            vec![json!(10)]
+        );
+        assert_eq!(select(".", &input).unwrap(), vec![input.clone()]);
+        assert_eq!(select(".missing", &input).unwrap(), vec![Value::Null]);
+    }
+
+    #[test]
+    fn rejects_bad_expressions() {
+        for expr in [
+            "",
+            ".items[",
+            ".items[x]",
+            ".a | nope",
+            ".a ]",
+            ".[?(.a =~ 1)]",
+            ".[\"a]",
+        ] {
+            assert!(expr.parse::<Selector>().is_err(), "{expr} should not parse");
+        }
+    }
+
+    #[test]
+    fn indexes_from_either_end() {
+        let input = sample();
+        assert_eq!(select(".items[0].id", &input).unwrap(), vec![json!(1)]);
+        assert_eq!(select(".items[-1].id", &input).unwrap(), vec![json!(3)]);
+        assert_eq!(select(".items[-3].id", &input).unwrap(), vec![json!(1)]);
+        assert_eq!(select(".items[-4]", &input).unwrap(), vec![Value::Null]);
+        assert_eq!(select(".items[3]", &input).unwrap(), vec![Value::Null]);
+        assert!(select(".token[0]", &input).is_err());
+    }
+
+    #[test]
+    fn wildcards_and_pipes() {
+        let input = sample();
+        assert_eq!(
+            select(".items[].id", &input).unwrap(),
+            vec![json!(1), json!(2), json!(3)]
+        );
+        assert_eq!(
+            select(".items[*].name", &input).unwrap(),
+            vec![json!("a"), json!("b"), json!("c")]
+        );
+        assert_eq!(
+            select(".\"by name\".*.id", &input).unwrap(),
+            vec![json!(10), json!(20)]
+        );
+        assert_eq!(select(".items | length", &input).unwrap(), vec![json!(3)]);
+        assert_eq!(
+            select(".\"by name\" | keys", &input).unwrap(),
+            vec![json!(["x", "y"])]
+        );
+        assert!(select(".token[]", &input).is_err());
+    }
+
+    #[test]
+    fn filters_arrays() {
+        let input = sample();
+        assert_eq!(
+            select(".items[?(.id >= 2)].name", &input).unwrap(),
+            vec![json!("b"), json!("c")]
+        );
+        assert_eq!(
+            select(".items[?(@.name == 'a')].id", &input).unwrap(),
+            vec![json!(1)]
+        );
+        assert_eq!(
+            select(".items[?(.active)].id", &input).unwrap(),
+            vec![json!(1)]
+        );
+        assert_eq!(
+            select(".items[?(.name != \"a\")].id", &input).unwrap(),
+            vec![json!(2), json!(3)]
+        );
+    }
+
+    #[test]
+    fn filters_object_values() {
+        let input = sample();
+        assert_eq!(
+            select(".\"by name\"[?(.id > 15)]", &input).unwrap(),
+            vec![json!({"id": 20})]
+        );
+        assert!(select(".token[?(.id)]", &input).is_err());
+    }
+
+    fn blank(expr: &str, input: &mut Value) {
+        expr.parse::<Selector>()
+            .unwrap()
+            .for_each_mut(input, &mut |value| *value = json!("***"))
+            .unwrap();
+    }
+
+    #[test]
+    fn for_each_mut_changes_matches_in_place() {
+        let mut input = sample();
+        blank(".items[?(.id == 2)].name", &mut input);
+        blank(".items[-1].id", &mut input);
+        blank(".\"by name\".*.id", &mut input);
+        blank(".token", &mut input);
+        assert_eq!(
+            input,
+            json!({
+                "items": [
+                    {"id": 1, "name": "a", "active": true},
+                    {"id": 2, "name": "***", "active": false},
+                    {"id": "***", "name": "c"}
+                ],
+                "by name": {"x": {"id": "***"}, "y": {"id": "***"}},
+                "token": "***"
+            })
+        );
+    }
+
+    #[test]
+    fn for_each_mut_skips_missing_paths() {
+        let mut input = sample();
+        blank(".nope.deeper", &mut input);
+        blank(".items[7].id", &mut input);
+        blank(".items[-9]", &mut input);
+        blank(".token.inner", &mut input);
+        blank(".items[?(.id > 99)].name", &mut input);
+        assert_eq!(input, sample());
+    }
+
+    #[test]
+    fn for_each_mut_refuses_functions() {
+        let mut input = sample();
+        let selector: Selector = ".items | length".parse().unwrap();
+        assert!(selector.for_each_mut(&mut input, &mut |_| ()).is_err());
+    }
+}
@@ -1,120 +1,1134 @@
-use std::collections::HashMap;
+mod composer;
+mod json_tree;
+mod keys;
+mod layout;
+mod metadata;
+mod sql_console;
+mod theme;
 
-use crate::db::{Db, to_ui_displayable};
-use colored::Colorize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::db::{Db, DbHandle, EntryFilter, RequestMetadata, RequestSummary};
+use crate::retention::RetentionPolicy;
+use crate::retry::RetryPolicy;
+use composer::{Composer, ComposerView};
+use json_tree::{JsonTree, JsonTreeView};
+use keys::{Action, HelpView, KeyList, Keymap};
+use layout::{Border, PaneAreas, PaneLayout, RESIZE_STEP, list_row};
+use metadata::{MetadataView, metadata_lines};
 use ratatui::layout::{Constraint, Direction};
-use ratatui::style::{Color, Modifier, Style};
-use ratatui::widgets::{List, ListItem, Widget};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{List, ListItem, ListState, StatefulWidget, Tabs, Widget};
 use ratatui::{
     DefaultTerminal, Frame,
     buffer::Buffer,
     crossterm::{self, event::KeyCode},
-    layout::{Layout, Rect},
+    layout::{Layout, Position, Rect},
     widgets::{Block, Borders, Paragraph},
 };
+use serde::Deserialize;
+use sql_console::{HISTORY_SIZE, MAX_ROWS, SqlConsole, SqlConsoleView};
+use theme::{Theme, ThemeConfig, set_theme, theme};
 
 pub struct Ui {}
 
+/// `[ui]` in the config file.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct UiConfig {
+    /// action name to key(s), e.g. `down = ["j", "Down"]`
+    pub keys: Option<HashMap<String, KeyList>>,
+    pub theme: Option<ThemeConfig>,
+}
+
 impl Ui {
     // db not abstracted so not very kosher, this should probably work through a common interface
     // in reality
-    pub fn run(db: &Db) -> color_eyre::Result<()> {
+    pub fn run(
+        db: Db,
+        http_client: Option<reqwest::Client>,
+        retry_policy: RetryPolicy,
+        retention: RetentionPolicy,
+        follow: bool,
+        config: UiConfig,
+    ) -> color_eyre::Result<()> {
+        let keys = match Keymap::from_config(config.keys.unwrap_or_default()) {
+            Ok(keys) => keys,
+            Err(err) => {
+                eprintln!("Couldn't read [ui.keys]: {}", err);
+                std::process::exit(1)
+            }
+        };
+        match Theme::from_config(config.theme.unwrap_or_default()) {
+            Ok(theme) => set_theme(theme),
+            Err(err) => {
+                eprintln!("Couldn't read [ui.theme]: {}", err);
+                std::process::exit(1)
+            }
+        }
+
         let _ = color_eyre::install();
-        ratatui::run(|terminal| ui_application(terminal, db))?;
+        let app = App::new(db, http_client, retry_policy, retention, follow, keys);
+        ratatui::run(|terminal| ui_application(terminal, app))?;
         Ok(())
     }
 }
 
+/// How many list rows are fetched from the db at a time
+const PAGE_SIZE: i64 = 100;
+/// How close the selection gets to the end of the loaded rows before the next page is fetched
+const PREFETCH_MARGIN: usize = 10;
+/// How many payload/response pairs are kept around
+const BODY_CACHE_SIZE: usize = 32;
+/// How often the db is checked for requests stored by other scooby processes
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Rows moved per mouse wheel notch
+const MOUSE_SCROLL: isize = 3;
+/// `ui_settings` key, the pane sizes live in `PaneLayout`
+const SHOW_METADATA_KEY: &str = "layout.show_metadata";
+
+// this is VERY hacky, but can't be bothered with the async await nonsense right now. The UI loop
+// is synchronous so db calls are driven to completion in place.
+fn block_on<F: Future>(future: F) -> F::Output {
+    tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(future))
+}
+
+#[derive(PartialEq, Eq)]
+enum Tab {
+    Requests,
+    Sql,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum FocusedWidget {
     List,
     Payload,
     Response,
+    Metadata,
+}
+
+#[derive(PartialEq, Eq)]
+enum InputMode {
+    Normal,
+    Search,
+    Filter,
+    /// search inside the focused payload/response pane in fullscreen
+    PaneSearch,
+    /// editing the note of the selected request
+    Note,
+    /// waiting for y/n before deleting the selected request
+    ConfirmDelete,
+}
+
+type Bodies = (Option<serde_json::Value>, Option<serde_json::Value>);
+
+/// Payloads and responses of the most recently selected requests, oldest gets evicted first.
+struct BodyCache {
+    order: VecDeque<i64>,
+    entries: HashMap<i64, Bodies>,
+}
+
+impl BodyCache {
+    fn new() -> Self {
+        Self {
+            order: VecDeque::new(),
+            entries: HashMap::new(),
+        }
+    }
+
+    fn get(&self, id: i64) -> Option<&Bodies> {
+        self.entries.get(&id)
+    }
+
+    fn insert(&mut self, id: i64, bodies: Bodies) {
+        if self.entries.insert(id, bodies).is_none() {
+            self.order.push_back(id);
+        }
+        while self.order.len() > BODY_CACHE_SIZE {
+            if let Some(oldest) = self.order.pop_front() {
+                self.entries.remove(&oldest);
+            }
+        }
+    }
 }
 
 /// Application state (which item is selected, etc.)
 struct App {
-    item_ids: Vec<String>,
-    item_contents: HashMap<String, Option<serde_json::Value>>,
-    item_responses: HashMap<String, Option<serde_json::Value>>,
+    /// only open while something is being done, so `scooby req` elsewhere can store requests
+    db: Option<Db>,
+    /// reopens `db` without running the migrations again, that happens on every key press
+    db_handle: DbHandle,
+    /// rows loaded so far for the current search/filter, newest first
+    items: Vec<RequestSummary>,
+    /// how many rows match the current search/filter in total
+    total: i64,
+    /// every matching row has been loaded
+    exhausted: bool,
+    /// newest id in the db at the last check, anything above it is new
+    newest_id: Option<i64>,
+    /// stored since the ui was opened and not selected yet
+    unseen: HashSet<i64>,
+    /// jump to new requests as they come in
+    follow: bool,
+    bodies: BodyCache,
     selected: usize,
     fullscreen: bool,
-    payload_scroll: u16,
-    response_scroll: u16,
+    payload_tree: JsonTree,
+    response_tree: JsonTree,
+    /// the urls/headers/timing pane is shown next to payload and response
+    show_metadata: bool,
+    /// only the selected request's, fetched when the pane is shown
+    metadata: Option<(i64, RequestMetadata)>,
+    metadata_scroll: u16,
+    layout: PaneLayout,
+    /// pane positions in the last frame, for the mouse
+    areas: PaneAreas,
+    /// border being dragged with the mouse
+    dragging: Option<Border>,
     focused_widget: FocusedWidget,
+    input_mode: InputMode,
+    search_query: String,
+    filter_input: String,
+    note_input: String,
+    filter: EntryFilter,
+    filter_error: Option<String>,
+    db_error: Option<String>,
+    composer: Option<Composer>,
+    tab: Tab,
+    sql: SqlConsole,
+    /// one-off message for the status line, cleared on the next key press
+    notice: Option<String>,
+    keys: Keymap,
+    /// the `?` overlay is open
+    show_help: bool,
+    /// `None` when there's no auth token, the composer can't send then
+    http_client: Option<reqwest::Client>,
+    retry_policy: RetryPolicy,
+    /// applied after every request sent from the composer
+    retention: RetentionPolicy,
 }
 
 impl App {
-    async fn new(db: &Db) -> Self {
-        let data = match db.get_all_entries().await {
-            Ok(data) => data,
+    fn new(
+        db: Db,
+        http_client: Option<reqwest::Client>,
+        retry_policy: RetryPolicy,
+        retention: RetentionPolicy,
+        follow: bool,
+        keys: Keymap,
+    ) -> Self {
+        let mut app = Self {
+            db_handle: db.handle(),
+            db: Some(db),
+            items: Vec::new(),
+            total: 0,
+            exhausted: false,
+            newest_id: None,
+            unseen: HashSet::new(),
+            follow,
+            bodies: BodyCache::new(),
+            selected: 0,
+            fullscreen: false,
+            payload_tree: JsonTree::default(),
+            response_tree: JsonTree::default(),
+            show_metadata: false,
+            metadata: None,
+            metadata_scroll: 0,
+            layout: PaneLayout::default(),
+            areas: PaneAreas::default(),
+            dragging: None,
+            focused_widget: FocusedWidget::List,
+            input_mode: InputMode::Normal,
+            search_query: String::new(),
+            filter_input: String::new(),
+            note_input: String::new(),
+            filter: EntryFilter::default(),
+            filter_error: None,
+            db_error: None,
+            composer: None,
+            tab: Tab::Requests,
+            sql: SqlConsole::default(),
+            notice: None,
+            keys,
+            show_help: false,
+            http_client,
+            retry_policy,
+            retention,
+        };
+        app.load_layout();
+        app.reload();
+        app
+    }
+
+    fn load_layout(&mut self) {
+        let settings = self.with_db(async |db| {
+            let mut settings = Vec::new();
+            for key in [
+                PaneLayout::LIST_WIDTH_KEY,
+                PaneLayout::PAYLOAD_SHARE_KEY,
+                SHOW_METADATA_KEY,
+            ] {
+                if let Some(value) = db.get_ui_setting(key).await? {
+                    settings.push((key, value));
+                }
+            }
+            Ok(settings)
+        });
+        match settings {
+            Ok(settings) => {
+                for (key, value) in settings {
+                    if key == SHOW_METADATA_KEY {
+                        self.show_metadata = value == "true";
+                    } else {
+                        self.layout.load(key, &value);
+                    }
+                }
+            }
+            Err(err) => self.db_error = Some(format!("couldn't load the layout: {err}")),
+        }
+    }
+
+    fn save_layout(&mut self) {
+        let settings = [
+            (
+                PaneLayout::LIST_WIDTH_KEY,
+                self.layout.list_width.to_string(),
+            ),
+            (
+                PaneLayout::PAYLOAD_SHARE_KEY,
+                self.layout.payload_share.to_string(),
+            ),
+            (SHOW_METADATA_KEY, self.show_metadata.to_string()),
+        ];
+        let saved = self.with_db(async |db| {
+            for (key, value) in &settings {
+                db.set_ui_setting(key, value).await?;
+            }
+            Ok(())
+        });
+        if let Err(err) = saved {
+            self.db_error = Some(format!("couldn't save the layout: {err}"));
+        }
+    }
+
+    /// `<`/`>` move the border left of the detail panes, `-`/`+` the one under the payload.
+    fn resize(&mut self, action: Action) {
+        let (border, delta) = match action {
+            Action::ResizeLeft | Action::ResizeRight if self.fullscreen => {
+                (Border::Payload, action == Action::ResizeRight)
+            }
+            Action::ResizeLeft | Action::ResizeRight => {
+                (Border::List, action == Action::ResizeRight)
+            }
+            // the panes are side by side in fullscreen, there's nothing above or below
+            _ if self.fullscreen => return,
+            _ => (Border::Payload, action == Action::ResizeDown),
+        };
+        let delta = if delta { RESIZE_STEP } else { -RESIZE_STEP };
+        self.layout.resize(border, delta);
+        self.save_layout();
+    }
+
+    fn handle_mouse(&mut self, event: crossterm::event::MouseEvent) {
+        use crossterm::event::{MouseButton, MouseEventKind};
+
+        let pos = Position::new(event.column, event.row);
+        match event.kind {
+            MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(border) = PaneLayout::border_at(&self.areas, pos) {
+                    self.dragging = Some(border);
+                } else {
+                    self.click(pos);
+                }
+            }
+            MouseEventKind::Drag(MouseButton::Left) => {
+                if let Some(border) = self.dragging {
+                    self.layout.drag(border, &self.areas, pos);
+                }
+            }
+            MouseEventKind::Up(MouseButton::Left) if self.dragging.take().is_some() => {
+                self.save_layout();
+            }
+            MouseEventKind::ScrollDown => self.scroll_at(pos, MOUSE_SCROLL),
+            MouseEventKind::ScrollUp => self.scroll_at(pos, -MOUSE_SCROLL),
+            _ => {}
+        }
+    }
+
+    /// A detail pane has focus, in fullscreen or after clicking one, so movement goes to it.
+    fn pane_focused(&self) -> bool {
+        self.focused_widget != FocusedWidget::List
+    }
+
+    fn pane_at(&self, pos: Position) -> Option<FocusedWidget> {
+        let areas = &self.areas;
+        if areas.list.is_some_and(|list| list.contains(pos)) {
+            Some(FocusedWidget::List)
+        } else if areas.payload.contains(pos) {
+            Some(FocusedWidget::Payload)
+        } else if areas.response.contains(pos) {
+            Some(FocusedWidget::Response)
+        } else if areas
+            .metadata
+            .is_some_and(|metadata| metadata.contains(pos))
+        {
+            Some(FocusedWidget::Metadata)
+        } else {
+            None
+        }
+    }
+
+    /// Clicking a request selects it, clicking a pane focuses it and moves its cursor there.
+    fn click(&mut self, pos: Position) {
+        let Some(pane) = self.pane_at(pos) else {
+            return;
+        };
+        match pane {
+            FocusedWidget::List => {
+                let Some(list) = self.areas.list else {
+                    return;
+                };
+                if let Some(row) = list_row(Some(self.selected), list, pos)
+                    && row < self.items.len()
+                    && row != self.selected
+                {
+                    self.selected = row;
+                    self.selection_changed();
+                }
+            }
+            FocusedWidget::Payload | FocusedWidget::Response => {
+                let area = if matches!(pane, FocusedWidget::Payload) {
+                    self.areas.payload
+                } else {
+                    self.areas.response
+                };
+                // the cursor is only drawn in the focused pane, so the others start at the top
+                let was_focused = self.focused_widget == pane;
+                self.focused_widget = pane;
+                self.with_focused_tree(|tree, value| {
+                    let cursor = was_focused.then(|| tree.cursor());
+                    if let Some(row) = list_row(cursor, area, pos) {
+                        tree.set_cursor(value, row);
+                    }
+                });
+                return;
+            }
+            FocusedWidget::Metadata => (),
+        }
+        self.focused_widget = pane;
+    }
+
+    fn scroll_at(&mut self, pos: Position, delta: isize) {
+        match self.pane_at(pos) {
+            Some(FocusedWidget::List) => {
+                self.focused_widget = FocusedWidget::List;
+                self.move_selection(delta);
+            }
+            Some(pane) => {
+                self.focused_widget = pane;
+                self.move_tree_cursor(delta);
+            }
+            None => (),
+        }
+    }
+
+    /// Runs `action` against the db, opening it first if it was released.
+    fn with_db<T>(
+        &mut self,
+        action: impl AsyncFnOnce(&Db) -> Result<T, Box<dyn Error>>,
+    ) -> Result<T, String> {
+        let db = match &mut self.db {
+            Some(db) => db,
+            None => {
+                let db = block_on(self.db_handle.reopen())
+                    .map_err(|err| format!("couldn't open the db: {err}"))?;
+                self.db.insert(db)
+            }
+        };
+        let result = block_on(action(db)).map_err(|err| err.to_string());
+        // the banner is about the last thing that went wrong, a query going through means the
+        // db is fine again
+        if result.is_ok() {
+            self.db_error = None;
+        }
+        result
+    }
+
+    /// Closes the db so other processes can get to it, it's reopened on the next query.
+    fn release_db(&mut self) {
+        self.db = None;
+    }
+
+    fn entry_filter(&self) -> EntryFilter {
+        EntryFilter {
+            search: Some(self.search_query.clone()),
+            ..self.filter.clone()
+        }
+    }
+
+    /// Throws away the loaded rows and starts paging again from the newest one.
+    fn reload(&mut self) {
+        let previous = self.selected_id();
+        let filter = self.entry_filter();
+
+        self.items.clear();
+        self.exhausted = false;
+        let counted = self.with_db(async |db| {
+            let total = db.count_entries(&filter).await?;
+            let newest_id = db.latest_id().await?;
+            Ok((total, newest_id))
+        });
+        match counted {
+            Ok((total, newest_id)) => {
+                self.total = total;
+                self.newest_id = newest_id;
+            }
             Err(err) => {
-                eprintln!("{}: {}", Colorize::red("No data to be rendered"), err);
-                eprintln!("Exiting...");
-                std::process::exit(1)
+                self.db_error = Some(format!("couldn't count requests: {err}"));
+                self.total = 0;
+            }
+        }
+        self.load_page();
+
+        // stay on the same request if it's still in the first page
+        self.selected = previous
+            .and_then(|id| self.items.iter().position(|item| item.id == id))
+            .unwrap_or(0);
+        self.reset_trees();
+        self.load_selected_bodies();
+    }
+
+    fn load_page(&mut self) {
+        if self.exhausted {
+            return;
+        }
+
+        let filter = self.entry_filter();
+        let offset = self.items.len() as i64;
+        match self.with_db(async |db| db.get_summaries_page(&filter, PAGE_SIZE, offset).await) {
+            Ok(page) => {
+                if (page.len() as i64) < PAGE_SIZE {
+                    self.exhausted = true;
+                }
+                self.items.extend(page);
             }
+            Err(err) => {
+                self.db_error = Some(format!("couldn't load requests: {err}"));
+                self.exhausted = true;
+            }
+        }
+    }
+
+    fn selected_id(&self) -> Option<i64> {
+        self.items.get(self.selected).map(|item| item.id)
+    }
+
+    fn load_selected_bodies(&mut self) {
+        self.load_selected_metadata();
+        let Some(id) = self.selected_id() else {
+            return;
         };
+        if self.bodies.get(id).is_some() {
+            return;
+        }
 
-        let display_data = to_ui_displayable(data);
+        match self.with_db(async |db| db.get_bodies(id).await) {
+            Ok(Some(bodies)) => self.bodies.insert(id, bodies),
+            Ok(None) => (),
+            Err(err) => self.db_error = Some(format!("couldn't load request {id}: {err}")),
+        }
+    }
 
-        Self {
-            item_ids: display_data.iter().map(|item| item.key.clone()).collect(),
-            item_contents: display_data
-                .iter()
-                .map(|item| (item.key.clone(), item.content.clone()))
-                .collect(),
-            item_responses: display_data
-                .iter()
-                .map(|item| (item.key.clone(), item.response.clone()))
-                .collect(),
-            selected: 0,
-            fullscreen: false,
-            payload_scroll: 0,
-            response_scroll: 0,
-            focused_widget: FocusedWidget::List,
+    fn load_selected_metadata(&mut self) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        if !self.show_metadata
+            || self
+                .metadata
+                .as_ref()
+                .is_some_and(|(cached, _)| *cached == id)
+        {
+            return;
+        }
+
+        match self.with_db(async |db| db.get_metadata(id).await) {
+            Ok(Some(metadata)) => self.metadata = Some((id, metadata)),
+            Ok(None) => self.metadata = None,
+            Err(err) => self.db_error = Some(format!("couldn't load request {id}: {err}")),
+        }
+    }
+
+    fn selected_metadata(&self) -> Option<&RequestMetadata> {
+        let id = self.selected_id()?;
+        self.metadata
+            .as_ref()
+            .filter(|(cached, _)| *cached == id)
+            .map(|(_, metadata)| metadata)
+    }
+
+    fn toggle_metadata(&mut self) {
+        self.show_metadata = !self.show_metadata;
+        if !self.show_metadata && matches!(self.focused_widget, FocusedWidget::Metadata) {
+            self.focused_widget = FocusedWidget::Payload;
+        }
+        self.load_selected_metadata();
+        self.save_layout();
+    }
+
+    fn scroll_metadata(&mut self, delta: isize) {
+        let len = self
+            .selected_metadata()
+            .map(|metadata| metadata_lines(metadata).len())
+            .unwrap_or(0);
+        self.metadata_scroll = (self.metadata_scroll as usize)
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1)) as u16;
+    }
+
+    fn reset_trees(&mut self) {
+        self.payload_tree = JsonTree::default();
+        self.response_tree = JsonTree::default();
+        self.metadata_scroll = 0;
+    }
+
+    /// Picks up requests stored since the last check and puts them on top of the list.
+    fn check_for_new(&mut self) {
+        let filter = EntryFilter {
+            after_id: Some(self.newest_id.unwrap_or(0)),
+            ..self.entry_filter()
+        };
+        let checked = self.with_db(async |db| {
+            let newest_id = db.latest_id().await?;
+            // more than a page worth of new rows between two ticks is unlikely, the rest would
+            // show up on the next reload anyway
+            let new = db.get_summaries_page(&filter, PAGE_SIZE, 0).await?;
+            Ok((newest_id, new))
+        });
+        let (newest_id, new) = match checked {
+            Ok(checked) => checked,
+            Err(err) => {
+                self.db_error = Some(format!("couldn't check for new requests: {err}"));
+                return;
+            }
+        };
+        self.newest_id = newest_id;
+        if new.is_empty() {
+            return;
+        }
+
+        let was_empty = self.items.is_empty();
+        self.total += new.len() as i64;
+        self.unseen.extend(new.iter().map(|item| item.id));
+        let count = new.len();
+        self.items.splice(0..0, new);
+
+        // don't pull the rug from under someone reading a response in fullscreen
+        if (self.follow && !self.fullscreen) || was_empty {
+            self.selected = 0;
+            self.selection_changed();
+        } else {
+            self.selected += count;
+        }
+    }
+
+    fn toggle_follow(&mut self) {
+        self.follow = !self.follow;
+        if self.follow && !self.fullscreen && !self.items.is_empty() {
+            self.selected = 0;
+            self.selection_changed();
+        }
+    }
+
+    fn selection_changed(&mut self) {
+        if let Some(id) = self.selected_id() {
+            self.unseen.remove(&id);
+        }
+        self.reset_trees();
+        if self.selected + PREFETCH_MARGIN >= self.items.len() {
+            self.load_page();
+        }
+        self.load_selected_bodies();
+    }
+
+    fn apply_filters(&mut self) {
+        match EntryFilter::parse(&self.filter_input) {
+            Ok(filter) => {
+                self.filter = filter;
+                self.filter_error = None;
+            }
+            // keep the last valid filter while the user is still typing
+            Err(err) => self.filter_error = Some(err),
+        }
+        self.reload();
+    }
+
+    fn start_input(&mut self, mode: InputMode) {
+        if self.fullscreen {
+            // the list isn't visible, `/` searches the focused pane instead
+            if mode == InputMode::Search
+                && self
+                    .with_focused_tree(|tree, _| tree.query.clear())
+                    .is_some()
+            {
+                self.input_mode = InputMode::PaneSearch;
+            }
+        } else {
+            self.input_mode = mode;
+        }
+    }
+
+    fn input_char(&mut self, c: char) {
+        match self.input_mode {
+            InputMode::Search => self.search_query.push(c),
+            InputMode::Filter => self.filter_input.push(c),
+            InputMode::Note => return self.note_input.push(c),
+            InputMode::PaneSearch => {
+                self.with_focused_tree(|tree, value| {
+                    tree.query.push(c);
+                    tree.jump_to_match(value, true, true);
+                });
+                return;
+            }
+            InputMode::Normal | InputMode::ConfirmDelete => return,
+        }
+        self.apply_filters();
+    }
+
+    fn input_backspace(&mut self) {
+        match self.input_mode {
+            InputMode::Search => self.search_query.pop(),
+            InputMode::Filter => self.filter_input.pop(),
+            InputMode::Note => {
+                self.note_input.pop();
+                return;
+            }
+            InputMode::PaneSearch => {
+                self.with_focused_tree(|tree, value| {
+                    tree.query.pop();
+                    tree.jump_to_match(value, true, true);
+                });
+                return;
+            }
+            InputMode::Normal | InputMode::ConfirmDelete => return,
+        };
+        self.apply_filters();
+    }
+
+    /// Enter keeps what was typed, Esc throws it away.
+    fn finish_input(&mut self, keep: bool) {
+        let mode = std::mem::replace(&mut self.input_mode, InputMode::Normal);
+        match mode {
+            InputMode::Note if keep => self.save_note(),
+            InputMode::Note | InputMode::Normal | InputMode::ConfirmDelete => (),
+            _ if keep => (),
+            InputMode::Search => {
+                self.search_query.clear();
+                self.apply_filters();
+            }
+            InputMode::Filter => {
+                self.filter_input.clear();
+                self.apply_filters();
+            }
+            InputMode::PaneSearch => {
+                self.with_focused_tree(|tree, _| tree.query.clear());
+            }
+        }
+    }
+
+    fn toggle_pin(&mut self) {
+        let Some(item) = self.items.get(self.selected) else {
+            return;
+        };
+        let (id, pinned) = (item.id, !item.pinned);
+        match self.with_db(async |db| db.set_pinned(id, pinned).await) {
+            Ok(_) => {
+                if let Some(item) = self.items.get_mut(self.selected) {
+                    item.pinned = pinned;
+                }
+                let verb = if pinned { "pinned" } else { "unpinned" };
+                self.notice = Some(format!("{} #{}", verb, id));
+            }
+            Err(err) => self.notice = Some(format!("couldn't pin #{}: {}", id, err)),
+        }
+    }
+
+    /// Starts editing the note of the selected request, prefilled with the current one.
+    fn start_note(&mut self) {
+        let Some(item) = self.items.get(self.selected) else {
+            return;
+        };
+        if self.fullscreen {
+            return;
+        }
+        self.note_input = item.note.clone().unwrap_or_default();
+        self.input_mode = InputMode::Note;
+    }
+
+    /// An empty note removes it.
+    fn save_note(&mut self) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        let note = Some(self.note_input.trim().to_string()).filter(|note| !note.is_empty());
+        match self.with_db(async |db| db.set_note(id, note.as_deref()).await) {
+            Ok(_) => {
+                if let Some(item) = self.items.get_mut(self.selected) {
+                    item.note = note;
+                }
+            }
+            Err(err) => self.notice = Some(format!("couldn't save the note: {}", err)),
+        }
+    }
+
+    fn start_delete(&mut self) {
+        let Some(item) = self.items.get(self.selected) else {
+            return;
+        };
+        if self.fullscreen {
+            return;
+        }
+        if item.pinned {
+            self.notice = Some(format!(
+                "#{} is pinned, unpin it with {} first",
+                item.id,
+                self.keys.label(Action::Pin)
+            ));
+            return;
+        }
+        self.input_mode = InputMode::ConfirmDelete;
+    }
+
+    fn confirm_delete(&mut self, confirmed: bool) {
+        self.input_mode = InputMode::Normal;
+        let Some(id) = self.selected_id().filter(|_| confirmed) else {
+            return;
+        };
+        match self.with_db(async |db| db.delete_entries(&[id]).await) {
+            Ok(_) => {
+                self.items.remove(self.selected);
+                self.total -= 1;
+                if self
+                    .metadata
+                    .as_ref()
+                    .is_some_and(|(loaded, _)| *loaded == id)
+                {
+                    self.metadata = None;
+                }
+                self.selected = self.selected.min(self.items.len().saturating_sub(1));
+                self.selection_changed();
+                self.notice = Some(format!("deleted #{}", id));
+            }
+            Err(err) => self.notice = Some(format!("couldn't delete #{}: {}", id, err)),
+        }
+    }
+
+    fn jump_to_match(&mut self, forward: bool) {
+        self.with_focused_tree(|tree, value| tree.jump_to_match(value, forward, false));
+    }
+
+    fn open_composer(&mut self) {
+        let Some(id) = self.selected_id() else {
+            return;
+        };
+        if self.fullscreen {
+            return;
+        }
+
+        let loaded = self.with_db(async |db| {
+            let request = db.get_entry_by_id(id).await?;
+            let metadata = db.get_metadata(id).await?;
+            Ok(request.map(|request| (request, metadata)))
+        });
+        match loaded {
+            Ok(Some((request, metadata))) => {
+                let request_headers = metadata.and_then(|metadata| metadata.request_headers);
+                self.composer = Some(Composer::from_request(&request, request_headers.as_ref()))
+            }
+            Ok(None) => self.db_error = Some(format!("request {id} is gone")),
+            Err(err) => self.db_error = Some(format!("couldn't load request {id}: {err}")),
+        }
+    }
+
+    /// Sends what's in the composer, stores it and selects the new entry on success.
+    fn send_composed(&mut self) {
+        let Some(composer) = self.composer.as_mut() else {
+            return;
+        };
+        let Some(http_client) = &self.http_client else {
+            composer.error = Some("no auth_token set, can't send requests".into());
+            return;
+        };
+        let request = match composer.build() {
+            Ok(request) => request,
+            Err(err) => {
+                composer.error = Some(err);
+                return;
+            }
+        };
+
+        let stored = block_on(composer::send(http_client, &self.retry_policy, request)).and_then(
+            |(store_args, parts)| {
+                self.with_db(async |db| db.insert_args(store_args, parts).await)
+                    .map_err(|err| format!("couldn't store the response: {err}"))
+            },
+        );
+
+        match stored {
+            Ok(id) => {
+                self.composer = None;
+                let retention = self.retention.clone();
+                if let Err(err) = self.with_db(async |db| db.apply_retention(&retention).await) {
+                    self.notice = Some(format!("couldn't prune old requests: {err}"));
+                }
+                self.reload();
+                if let Some(position) = self.items.iter().position(|item| item.id == id) {
+                    self.selected = position;
+                    self.load_selected_bodies();
+                }
+            }
+            Err(err) => {
+                if let Some(composer) = self.composer.as_mut() {
+                    composer.error = Some(err);
+                }
+            }
+        }
+    }
+
+    fn open_sql_tab(&mut self) {
+        self.tab = Tab::Sql;
+        if self.sql.history_loaded {
+            return;
+        }
+        match self.with_db(async |db| db.get_sql_history(HISTORY_SIZE).await) {
+            Ok(history) => self.sql.history = history,
+            Err(err) => self.sql.error = Some(format!("couldn't load history: {err}")),
+        }
+        self.sql.history_loaded = true;
+    }
+
+    fn close_sql_tab(&mut self) {
+        self.tab = Tab::Requests;
+    }
+
+    fn run_sql(&mut self) {
+        let query = self.sql.input.trim().to_string();
+        if query.is_empty() {
+            return;
+        }
+        let allow_writes = self.sql.writes_enabled;
+
+        let started = Instant::now();
+        let output = self.with_db(async |db| db.run_sql(&query, allow_writes, MAX_ROWS).await);
+        let elapsed_ms = started.elapsed().as_millis();
+
+        if self.sql.history.last() != Some(&query) {
+            // losing a history entry isn't worth bothering anyone about
+            let _ = self.with_db(async |db| db.add_sql_history(&query).await);
+        }
+        self.sql.remember(&query);
+        // a failed query stays in the input so it can be fixed
+        if output.is_ok() {
+            self.sql.input.clear();
+        }
+        self.sql.show(output, elapsed_ms);
+
+        if allow_writes {
+            // whatever was changed should show up in the list
+            self.reload();
+        }
+    }
+
+    fn status_line(&self) -> String {
+        if self.tab == Tab::Sql {
+            return self.sql.status_line();
+        }
+        if let Some(composer) = &self.composer {
+            return match &composer.error {
+                Some(err) => format!("compose: {}", err),
+                None => {
+                    "compose  Tab next field  Enter edit in $EDITOR  Ctrl+s send  Esc cancel".into()
+                }
+            };
+        }
+        if let Some(notice) = &self.notice {
+            return notice.clone();
+        }
+        if let Some(err) = &self.db_error {
+            return format!("db error: {}", err);
+        }
+
+        let pane_search = self.focused_tree().map(|(tree, value)| {
+            let (current, total) = tree.match_position(value);
+            (tree.query.as_str(), format!("{}/{}", current, total))
+        });
+        if let Some((query, matches)) = &pane_search {
+            if self.input_mode == InputMode::PaneSearch {
+                return format!("/{}  [{}]", query, matches);
+            }
+            if self.fullscreen && !query.is_empty() {
+                return format!("/{}  [{}]  n next  N previous", query, matches);
+            }
+        }
+
+        let counts = format!("{}/{}", self.items.len(), self.total);
+        match self.input_mode {
+            InputMode::Search => format!("/{}  [{}]", self.search_query, counts),
+            InputMode::Filter => match &self.filter_error {
+                Some(err) => format!("filter: {}  ({})", self.filter_input, err),
+                None => format!("filter: {}  [{}]", self.filter_input, counts),
+            },
+            InputMode::Note => format!("note: {}", self.note_input),
+            InputMode::ConfirmDelete => match self.selected_id() {
+                Some(id) => format!("delete #{}? y/n", id),
+                None => String::new(),
+            },
+            InputMode::Normal | InputMode::PaneSearch => {
+                let mut parts = Vec::new();
+                if !self.search_query.is_empty() {
+                    parts.push(format!("/{}", self.search_query));
+                }
+                if !self.filter_input.is_empty() {
+                    parts.push(format!("filter: {}", self.filter_input));
+                }
+                if parts.is_empty() {
+                    let hint = |action| self.keys.label(action);
+                    format!(
+                        "[{}]  {} search  {} filter  {} meta  {} sql  {} help  {} quit",
+                        counts,
+                        hint(Action::Search),
+                        hint(Action::Filter),
+                        hint(Action::Metadata),
+                        hint(Action::NextPane),
+                        hint(Action::Help),
+                        hint(Action::Quit),
+                    )
+                } else {
+                    format!("{}  [{}]", parts.join("  "), counts)
+                }
+            }
         }
     }
 
     fn next(&mut self) {
-        if !self.item_ids.is_empty() && !self.fullscreen {
-            self.selected = (self.selected + 1) % self.item_ids.len();
+        if !self.items.is_empty() && !self.fullscreen {
+            if self.selected + 1 < self.items.len() {
+                self.selected += 1;
+            } else {
+                self.load_page();
+                self.selected = (self.selected + 1) % self.items.len();
+            }
+            self.selection_changed();
+        }
+    }
+
+    /// Moves the selection by `delta` rows without wrapping, for page up/down and home/end.
+    fn move_selection(&mut self, delta: isize) {
+        if self.items.is_empty() || self.fullscreen {
+            return;
+        }
+        let selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.items.len() - 1);
+        if selected != self.selected {
+            self.selected = selected;
+            self.selection_changed();
         }
     }
 
     fn previous(&mut self) {
-        if !self.item_ids.is_empty() && !self.fullscreen {
-            if self.selected == 0 {
-                self.selected = self.item_ids.len() - 1;
-            } else {
+        if !self.items.is_empty() && !self.fullscreen {
+            if self.selected > 0 {
                 self.selected -= 1;
+            } else if self.exhausted {
+                // only wrap around once everything is loaded, otherwise the whole history would
+                // have to be fetched to find the last row
+                self.selected = self.items.len() - 1;
             }
+            self.selection_changed();
         }
     }
 
     fn toggle_fullscreen(&mut self) {
         self.fullscreen = !self.fullscreen;
+        // the list isn't visible in fullscreen, the trees are what can be navigated there
+        self.focused_widget = if self.fullscreen {
+            FocusedWidget::Payload
+        } else {
+            FocusedWidget::List
+        };
     }
 
-    fn scroll_focused(&mut self, delta: i16) {
+    fn focused_tree(&self) -> Option<(&JsonTree, Option<&serde_json::Value>)> {
+        let bodies = self.selected_id().and_then(|id| self.bodies.get(id));
         match self.focused_widget {
-            FocusedWidget::Payload => {
-                let new = self.payload_scroll as i16 + delta;
-                self.payload_scroll = new.max(0) as u16;
-            }
-            FocusedWidget::Response => {
-                let new = self.response_scroll as i16 + delta;
-                self.response_scroll = new.max(0) as u16;
-            }
-            // don't do anything for list
-            _ => {}
+            FocusedWidget::Payload => Some((
+                &self.payload_tree,
+                bodies.and_then(|(payload, _)| payload.as_ref()),
+            )),
+            FocusedWidget::Response => Some((
+                &self.response_tree,
+                bodies.and_then(|(_, response)| response.as_ref()),
+            )),
+            FocusedWidget::List | FocusedWidget::Metadata => None,
+        }
+    }
+
+    /// Runs `action` on the tree of the focused pane along with the json it shows.
+    fn with_focused_tree<T>(
+        &mut self,
+        action: impl FnOnce(&mut JsonTree, Option<&serde_json::Value>) -> T,
+    ) -> Option<T> {
+        let bodies = self.selected_id().and_then(|id| self.bodies.get(id));
+        match self.focused_widget {
+            FocusedWidget::Payload => Some(action(
+                &mut self.payload_tree,
+                bodies.and_then(|(payload, _)| payload.as_ref()),
+            )),
+            FocusedWidget::Response => Some(action(
+                &mut self.response_tree,
+                bodies.and_then(|(_, response)| response.as_ref()),
+            )),
+            // don't do anything for list, metadata isn't a tree
+            FocusedWidget::List | FocusedWidget::Metadata => None,
+        }
+    }
+
+    fn move_tree_cursor(&mut self, delta: isize) {
+        if matches!(self.focused_widget, FocusedWidget::Metadata) {
+            self.scroll_metadata(delta);
+            return;
+        }
+        self.with_focused_tree(|tree, value| tree.move_cursor(value, delta));
+    }
+
+    fn set_tree_node_open(&mut self, open: Option<bool>) {
+        self.with_focused_tree(|tree, value| tree.set_open(value, open));
+    }
+
+    fn copy_tree_path(&mut self) {
+        let path = self
+            .with_focused_tree(|tree, value| tree.current_path(value))
+            .flatten();
+        if let Some(path) = path {
+            self.notice = Some(match copy_to_clipboard(&path) {
+                Ok(()) => format!("copied {}", path),
+                Err(err) => format!("couldn't copy {}: {}", path, err),
+            });
         }
     }
 
     fn focus_next(&mut self) {
         if self.fullscreen {
-            // in fullscreen, only Payload <-> Response are meaningful
+            // in fullscreen, only the detail panes are meaningful
             self.focused_widget = match self.focused_widget {
                 FocusedWidget::Payload => FocusedWidget::Response,
-                FocusedWidget::Response => FocusedWidget::Payload,
+                FocusedWidget::Response if self.show_metadata => FocusedWidget::Metadata,
+                FocusedWidget::Response | FocusedWidget::Metadata => FocusedWidget::Payload,
                 // if fullscreen but focus is somehow on List, move to Payload.
                 FocusedWidget::List => FocusedWidget::Payload,
             }
@@ -123,205 +1137,456 @@ impl App {
     }
 }
 
+fn list_label(item: &RequestSummary) -> String {
+    let status = item
+        .status
+        .map(|status| status.to_string())
+        .unwrap_or_else(|| "---".into());
+    format!(
+        "#{} {} {} {} {} {}",
+        item.id,
+        item.method,
+        status,
+        item.service,
+        item.route_url,
+        item.created_at.format("%Y-%m-%d %H:%M:%S"),
+    )
+}
+
 struct Grid<'a> {
-    item_ids: &'a [String],
-    item_contents: &'a HashMap<String, Option<serde_json::Value>>,
-    item_responses: &'a HashMap<String, Option<serde_json::Value>>,
+    items: &'a [RequestSummary],
+    bodies: Option<&'a Bodies>,
+    unseen: &'a HashSet<i64>,
+    follow: bool,
     selected: usize,
-    fullscreen: bool,
-    payload_scroll: u16,
-    response_scroll: u16,
+    payload_tree: &'a JsonTree,
+    response_tree: &'a JsonTree,
+    /// `None` when the metadata pane is hidden
+    metadata: Option<Result<&'a RequestMetadata, &'a str>>,
+    metadata_scroll: u16,
     focused_widget: &'a FocusedWidget,
+    status_line: String,
+    editing: bool,
+    areas: PaneAreas,
 }
 
 impl Widget for Grid<'_> {
     fn render(self, area: Rect, buf: &mut Buffer) {
-        if self.fullscreen {
-            // Fullscreen view: split the whole area into two vertical panes (left/right)
-            let layout = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
-                .split(area);
-
-            let left_area = layout[0];
-            let right_area = layout[1];
-
-            let payload_content = if self.item_ids.is_empty() {
-                "No requests".to_string()
-            } else {
-                let key = &self.item_ids[self.selected];
-                match self.item_contents.get(key) {
-                    Some(Some(value)) => serde_json::to_string_pretty(value)
-                        .unwrap_or_else(|_| "<invalid json>".into()),
-                    Some(None) => "<no content>".into(),
-                    None => "<missing content>".into(),
-                }
-            };
+        let status_area = Rect {
+            y: area.bottom().saturating_sub(1),
+            height: area.height.min(1),
+            ..area
+        };
 
-            let response_content = if self.item_ids.is_empty() {
-                "No requests".to_string()
-            } else {
-                let key = &self.item_ids[self.selected];
-                match self.item_responses.get(key) {
-                    Some(Some(value)) => serde_json::to_string_pretty(value)
-                        .unwrap_or_else(|_| "<invalid json>".into()),
-                    Some(None) => "<no response>".into(),
-                    None => "<missing response>".into(),
-                }
-            };
+        let status_style = if self.editing {
+            Style::default().fg(theme().highlight)
+        } else {
+            Style::default().fg(theme().muted)
+        };
+        Paragraph::new(self.status_line)
+            .style(status_style)
+            .render(status_area, buf);
 
-            let mut payload_block = Block::default().borders(Borders::ALL).title("Payload");
-            let mut response_block = Block::default().borders(Borders::ALL).title("Response");
-            if matches!(self.focused_widget, FocusedWidget::Payload) {
-                payload_block = payload_block.border_style(
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD),
-                );
+        let (payload, response) = if self.items.is_empty() {
+            (Err("No requests"), Err("No requests"))
+        } else {
+            match self.bodies {
+                Some((payload, response)) => (
+                    payload.as_ref().ok_or("<no content>"),
+                    response.as_ref().ok_or("<no response>"),
+                ),
+                None => (Err("<missing content>"), Err("<missing response>")),
             }
-            if matches!(self.focused_widget, FocusedWidget::Response) {
-                response_block = response_block.border_style(
+        };
+
+        let focused_block = |title: &'static str, focused: bool| {
+            let block = Block::default().borders(Borders::ALL).title(title);
+            if focused {
+                block.border_style(
                     Style::default()
-                        .fg(Color::Cyan)
+                        .fg(theme().accent)
                         .add_modifier(Modifier::BOLD),
-                );
+                )
+            } else {
+                block
             }
+        };
+        let payload_focused = matches!(self.focused_widget, FocusedWidget::Payload);
+        let response_focused = matches!(self.focused_widget, FocusedWidget::Response);
+        let payload_widget = JsonTreeView {
+            value: payload,
+            tree: self.payload_tree,
+            block: focused_block("Payload", payload_focused),
+            focused: payload_focused,
+        };
+        let response_widget = JsonTreeView {
+            value: response,
+            tree: self.response_tree,
+            block: focused_block("Response", response_focused),
+            focused: response_focused,
+        };
+        let metadata_focused = matches!(self.focused_widget, FocusedWidget::Metadata);
+        let metadata_widget = self.metadata.map(|metadata| MetadataView {
+            metadata,
+            block: focused_block("Metadata", metadata_focused),
+            scroll: self.metadata_scroll,
+        });
+        // sizes come from `PaneLayout`, fullscreen puts the panes side by side without the list
+        let areas = self.areas;
+        payload_widget.render(areas.payload, buf);
+        response_widget.render(areas.response, buf);
+        if let (Some(metadata_widget), Some(metadata_area)) = (metadata_widget, areas.metadata) {
+            metadata_widget.render(metadata_area, buf);
+        }
 
-            let payload_widget = Paragraph::new(payload_content)
-                .block(payload_block)
-                .scroll((self.payload_scroll, 0));
-            let response_widget = Paragraph::new(response_content)
-                .block(response_block)
-                .scroll((self.response_scroll, 0));
-
-            payload_widget.render(left_area, buf);
-            response_widget.render(right_area, buf);
-        } else {
-            // Original grid view
-            let layout = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
-                .split(area);
-
-            let left_area = layout[0];
-            let right_area = layout[1];
-
-            let right_layout = Layout::default()
-                .direction(Direction::Vertical)
-                .constraints([Constraint::Ratio(1, 2), Constraint::Ratio(1, 2)])
-                .split(right_area);
-
-            let right_top = right_layout[0];
-            let right_bottom = right_layout[1];
-
+        if let Some(list_area) = areas.list {
             let items: Vec<ListItem> = self
-                .item_ids
+                .items
                 .iter()
                 .enumerate()
-                .map(|(idx, text)| {
+                .map(|(idx, item)| {
+                    let unseen = self.unseen.contains(&item.id);
                     let style = if idx == self.selected {
                         Style::default()
-                            .fg(Color::Yellow)
+                            .fg(theme().highlight)
                             .add_modifier(Modifier::BOLD)
+                    } else if unseen {
+                        Style::default().fg(theme().new)
                     } else {
                         Style::default()
                     };
-                    ListItem::new(text.clone()).style(style)
+                    let mut spans = Vec::new();
+                    if unseen {
+                        spans.push(Span::raw("● "));
+                    }
+                    if item.pinned {
+                        spans.push(Span::styled("★ ", Style::default().fg(theme().warning)));
+                    }
+                    spans.push(Span::raw(list_label(item)));
+                    for tag in &item.tags {
+                        spans.push(Span::raw(" "));
+                        spans.push(Span::styled(
+                            format!(" {} ", tag),
+                            Style::default()
+                                .fg(theme().accent)
+                                .add_modifier(Modifier::REVERSED),
+                        ));
+                    }
+                    if let Some(note) = &item.note {
+                        spans.push(Span::styled(
+                            format!("  {}", note),
+                            Style::default()
+                                .fg(theme().muted)
+                                .add_modifier(Modifier::ITALIC),
+                        ));
+                    }
+                    ListItem::new(Line::from(spans)).style(style)
                 })
                 .collect();
 
-            let list_block = Block::default().borders(Borders::ALL).title("Requests");
+            let mut title = "Requests".to_string();
+            if !self.unseen.is_empty() {
+                title.push_str(&format!(" ({} new)", self.unseen.len()));
+            }
+            if self.follow {
+                title.push_str(" [following]");
+            }
+            let list_block = Block::default().borders(Borders::ALL).title(title);
             let list = List::new(items).block(list_block);
 
-            list.render(left_area, buf);
+            // the state only exists to keep the selected row scrolled into view
+            let mut list_state = ListState::default().with_selected(Some(self.selected));
+            StatefulWidget::render(list, list_area, buf, &mut list_state);
+        }
+    }
+}
 
-            let payload_content = if self.item_ids.is_empty() {
-                "No requests".to_string()
-            } else {
-                let key = &self.item_ids[self.selected];
-                match self.item_contents.get(key) {
-                    Some(Some(value)) => serde_json::to_string_pretty(value)
-                        .unwrap_or_else(|_| "<invalid json>".into()),
-                    Some(None) => "<no content>".into(),
-                    None => "<missing content>".into(),
-                }
-            };
+/// Puts `text` on the clipboard with an OSC 52 escape, which the terminal takes care of. That
+/// also works over ssh, but some terminals need it enabled first.
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    use base64::Engine;
+    use std::io::Write;
 
-            let payload_block = Block::default().borders(Borders::ALL).title("Payload");
-            let payload_widget = Paragraph::new(payload_content)
-                .block(payload_block)
-                .scroll((self.payload_scroll, 0));
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", encoded)?;
+    stdout.flush()
+}
 
-            payload_widget.render(right_top, buf);
+/// Hands the terminal over to `$EDITOR` (vi if unset) to edit `text`.
+fn edit_in_editor(terminal: &mut DefaultTerminal, text: &str) -> io::Result<String> {
+    let path = std::env::temp_dir().join(format!("scooby-compose-{}.txt", std::process::id()));
+    std::fs::write(&path, text)?;
 
-            let response_content = if self.item_ids.is_empty() {
-                "No requests".to_string()
-            } else {
-                let key = &self.item_ids[self.selected];
-                match self.item_responses.get(key) {
-                    Some(Some(value)) => serde_json::to_string_pretty(value)
-                        .unwrap_or_else(|_| "<invalid json>".into()),
-                    Some(None) => "<no response>".into(),
-                    None => "<missing response>".into(),
-                }
-            };
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".into());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+
+    crossterm::terminal::disable_raw_mode()?;
+    crossterm::execute!(
+        io::stdout(),
+        crossterm::event::DisableMouseCapture,
+        crossterm::terminal::LeaveAlternateScreen
+    )?;
+    let status = std::process::Command::new(program)
+        .args(parts)
+        .arg(&path)
+        .status();
+    crossterm::execute!(
+        io::stdout(),
+        crossterm::terminal::EnterAlternateScreen,
+        crossterm::event::EnableMouseCapture
+    )?;
+    crossterm::terminal::enable_raw_mode()?;
+    terminal.clear()?;
+
+    let edited = match status {
+        Ok(status) if status.success() => std::fs::read_to_string(&path),
+        Ok(status) => Err(io::Error::other(format!("{program} exited with {status}"))),
+        Err(err) => Err(err),
+    };
+    let _ = std::fs::remove_file(&path);
+    edited
+}
 
-            let response_block = Block::default().borders(Borders::ALL).title("Response");
-            let response_widget = Paragraph::new(response_content)
-                .block(response_block)
-                .scroll((self.response_scroll, 0));
+fn handle_composer_key(
+    terminal: &mut DefaultTerminal,
+    app: &mut App,
+    key: crossterm::event::KeyEvent,
+) -> io::Result<()> {
+    let Some(composer) = app.composer.as_mut() else {
+        return Ok(());
+    };
 
-            response_widget.render(right_bottom, buf);
+    match key.code {
+        KeyCode::Esc => app.composer = None,
+        KeyCode::Tab => composer.next_field(),
+        KeyCode::BackTab => composer.previous_field(),
+        KeyCode::Char('s')
+            if key
+                .modifiers
+                .contains(crossterm::event::KeyModifiers::CONTROL) =>
+        {
+            composer.error = Some("sending...".into());
+            terminal.draw(|frame| render(frame, app))?;
+            app.send_composed();
+            // retries report on stderr, redraw everything in case they scribbled over the ui
+            terminal.clear()?;
+        }
+        KeyCode::Enter if composer.field_is_multiline() => {
+            let current = composer.multiline_text_mut().cloned().unwrap_or_default();
+            match edit_in_editor(terminal, &current) {
+                Ok(edited) => {
+                    if let Some(text) = composer.multiline_text_mut() {
+                        *text = edited.trim_end().to_string();
+                    }
+                    composer.error = None;
+                }
+                Err(err) => composer.error = Some(format!("editor failed: {err}")),
+            }
         }
+        KeyCode::Backspace => composer.input_backspace(),
+        KeyCode::Char(c) => composer.input_char(c),
+        _ => {}
     }
+    Ok(())
 }
 
-fn ui_application(terminal: &mut DefaultTerminal, db: &Db) -> std::io::Result<()> {
-    // this is VERY hacky, but can't be bothered with the async await nonsense right now. Passing
-    // the db like this by reference isn't terribly smart as it is.
-    let mut app =
-        tokio::task::block_in_place(|| tokio::runtime::Handle::current().block_on(App::new(db)));
+fn handle_sql_key(app: &mut App, key: crossterm::event::KeyEvent) {
+    let ctrl = key
+        .modifiers
+        .contains(crossterm::event::KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => app.close_sql_tab(),
+        KeyCode::Enter => app.run_sql(),
+        KeyCode::Up => app.sql.history_previous(),
+        KeyCode::Down => app.sql.history_next(),
+        KeyCode::Left => app.sql.scroll_columns(-1),
+        KeyCode::Right => app.sql.scroll_columns(1),
+        KeyCode::PageUp => app.sql.scroll_rows(-10),
+        KeyCode::PageDown => app.sql.scroll_rows(10),
+        KeyCode::Char('u') if ctrl => app.sql.scroll_rows(-10),
+        KeyCode::Char('d') if ctrl => app.sql.scroll_rows(10),
+        KeyCode::Char('w') if ctrl => app.sql.writes_enabled = !app.sql.writes_enabled,
+        KeyCode::Backspace => app.sql.input_backspace(),
+        KeyCode::Char(c) => app.sql.input_char(c),
+        _ => {}
+    }
+}
+
+fn ui_application(terminal: &mut DefaultTerminal, app: App) -> io::Result<()> {
+    crossterm::execute!(io::stdout(), crossterm::event::EnableMouseCapture)?;
+    let result = event_loop(terminal, app);
+    crossterm::execute!(io::stdout(), crossterm::event::DisableMouseCapture)?;
+    result
+}
 
+fn event_loop(terminal: &mut DefaultTerminal, mut app: App) -> io::Result<()> {
+    let mut last_check = Instant::now();
     loop {
-        terminal.draw(|frame| render(frame, &app))?;
+        // checked between key presses too, typing shouldn't hold back new requests
+        if last_check.elapsed() >= POLL_INTERVAL {
+            app.check_for_new();
+            last_check = Instant::now();
+        }
+
+        terminal.draw(|frame| render(frame, &mut app))?;
+        app.release_db();
 
-        if let crossterm::event::Event::Key(key) = crossterm::event::read()? {
+        if !crossterm::event::poll(POLL_INTERVAL.saturating_sub(last_check.elapsed()))? {
+            continue;
+        }
+
+        let key = match crossterm::event::read()? {
+            crossterm::event::Event::Key(key) => key,
+            // the overlays and the sql tab are keyboard only
+            crossterm::event::Event::Mouse(mouse)
+                if app.tab == Tab::Requests && app.composer.is_none() && !app.show_help =>
+            {
+                app.handle_mouse(mouse);
+                continue;
+            }
+            _ => continue,
+        };
+        app.notice = None;
+        if app.show_help {
+            app.show_help = false;
+            continue;
+        }
+        if app.composer.is_some() {
+            handle_composer_key(terminal, &mut app, key)?;
+            continue;
+        }
+        if app.tab == Tab::Sql {
+            handle_sql_key(&mut app, key);
+            continue;
+        }
+
+        if app.input_mode == InputMode::ConfirmDelete {
+            app.confirm_delete(matches!(key.code, KeyCode::Char('y' | 'Y')));
+            continue;
+        }
+        if app.input_mode != InputMode::Normal {
             match key.code {
-                KeyCode::Char('q') => break Ok(()),
-                KeyCode::Char('j') => app.next(),
-                KeyCode::Char('k') => app.previous(),
-                KeyCode::Enter => app.toggle_fullscreen(),
-                KeyCode::Tab => app.focus_next(),
-                KeyCode::Char('u')
-                    if key
-                        .modifiers
-                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                {
-                    app.scroll_focused(-1)
-                }
-                KeyCode::Char('d')
-                    if key
-                        .modifiers
-                        .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                {
-                    app.scroll_focused(1)
-                }
+                KeyCode::Enter => app.finish_input(true),
+                KeyCode::Esc => app.finish_input(false),
+                KeyCode::Backspace => app.input_backspace(),
+                KeyCode::Char(c) => app.input_char(c),
                 _ => {}
             }
+            continue;
+        }
+
+        let Some(action) = app.keys.action(&key) else {
+            continue;
+        };
+        match action {
+            Action::Quit => break Ok(()),
+            Action::Search => app.start_input(InputMode::Search),
+            Action::Filter => app.start_input(InputMode::Filter),
+            Action::Compose => app.open_composer(),
+            Action::Follow => app.toggle_follow(),
+            Action::Metadata => app.toggle_metadata(),
+            Action::Pin => app.toggle_pin(),
+            Action::Note => app.start_note(),
+            Action::Delete => app.start_delete(),
+            Action::Help => app.show_help = true,
+            Action::Down if app.pane_focused() => app.move_tree_cursor(1),
+            Action::Up if app.pane_focused() => app.move_tree_cursor(-1),
+            Action::Down => app.next(),
+            Action::Up => app.previous(),
+            Action::PageDown if app.pane_focused() => app.move_tree_cursor(10),
+            Action::PageUp if app.pane_focused() => app.move_tree_cursor(-10),
+            Action::PageDown => app.move_selection(10),
+            Action::PageUp => app.move_selection(-10),
+            Action::Top if app.pane_focused() => app.move_tree_cursor(isize::MIN),
+            Action::Bottom if app.pane_focused() => app.move_tree_cursor(isize::MAX),
+            Action::Top => app.move_selection(isize::MIN),
+            Action::Bottom => app.move_selection(isize::MAX),
+            Action::Collapse => app.set_tree_node_open(Some(false)),
+            Action::Expand => app.set_tree_node_open(Some(true)),
+            Action::Toggle => app.set_tree_node_open(None),
+            Action::CopyPath => app.copy_tree_path(),
+            Action::NextMatch => app.jump_to_match(true),
+            Action::PreviousMatch => app.jump_to_match(false),
+            Action::Fullscreen => app.toggle_fullscreen(),
+            Action::NextPane if !app.fullscreen => app.open_sql_tab(),
+            Action::NextPane => app.focus_next(),
+            Action::ResizeLeft | Action::ResizeRight | Action::ResizeUp | Action::ResizeDown => {
+                app.resize(action)
+            }
         }
     }
 }
 
-fn render(frame: &mut Frame, app: &App) {
+fn render(frame: &mut Frame, app: &mut App) {
+    let layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(1), Constraint::Min(0)])
+        .split(frame.area());
+
+    let selected_tab = match app.tab {
+        Tab::Requests => 0,
+        Tab::Sql => 1,
+    };
+    let tabs = Tabs::new(["Requests", "SQL"])
+        .select(selected_tab)
+        .highlight_style(
+            Style::default()
+                .fg(theme().highlight)
+                .add_modifier(Modifier::BOLD),
+        );
+    frame.render_widget(tabs, layout[0]);
+
+    if app.tab == Tab::Sql {
+        let sql_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(layout[1]);
+        frame.render_widget(SqlConsoleView { console: &app.sql }, sql_layout[0]);
+        frame.render_widget(
+            Paragraph::new(app.status_line()).style(Style::default().fg(theme().highlight)),
+            sql_layout[1],
+        );
+        return;
+    }
+
+    // everything but the status bar at the bottom
+    let grid_area = Rect {
+        height: layout[1].height.saturating_sub(1),
+        ..layout[1]
+    };
+    app.areas = app
+        .layout
+        .areas(grid_area, app.fullscreen, app.show_metadata);
+
     let grid = Grid {
-        item_ids: &app.item_ids,
-        item_contents: &app.item_contents,
-        item_responses: &app.item_responses,
+        items: &app.items,
+        bodies: app.selected_id().and_then(|id| app.bodies.get(id)),
+        unseen: &app.unseen,
+        follow: app.follow,
         selected: app.selected,
-        fullscreen: app.fullscreen,
-        payload_scroll: app.payload_scroll,
-        response_scroll: app.response_scroll,
+        payload_tree: &app.payload_tree,
+        response_tree: &app.response_tree,
+        metadata: app.show_metadata.then(|| {
+            if app.items.is_empty() {
+                Err("No requests")
+            } else {
+                app.selected_metadata().ok_or("<missing metadata>")
+            }
+        }),
+        metadata_scroll: app.metadata_scroll,
         focused_widget: &app.focused_widget,
+        status_line: app.status_line(),
+        editing: app.input_mode != InputMode::Normal || app.composer.is_some(),
+        areas: app.areas,
     };
-    frame.render_widget(grid, frame.area());
+    frame.render_widget(grid, layout[1]);
+
+    if let Some(composer) = &app.composer {
+        frame.render_widget(ComposerView { composer }, frame.area());
+    }
+    if app.show_help {
+        frame.render_widget(HelpView { keymap: &app.keys }, frame.area());
+    }
 }
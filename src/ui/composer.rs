@@ -0,0 +1,357 @@
+use std::time::Instant;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
+};
+use reqwest::{
+    Client, Method, Url,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+
+use crate::db::{DbStoreArgs, ScoobyRequest};
+use crate::http::{ResponseParts, builder_headers, send_with_retry, split_http_response};
+use crate::redact::{REDACTED, redactor};
+use crate::retry::RetryPolicy;
+
+use super::theme::theme;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ComposerField {
+    Method,
+    Url,
+    Headers,
+    Body,
+}
+
+impl ComposerField {
+    const ALL: [ComposerField; 4] = [
+        ComposerField::Method,
+        ComposerField::Url,
+        ComposerField::Headers,
+        ComposerField::Body,
+    ];
+
+    fn index(self) -> usize {
+        Self::ALL.iter().position(|f| *f == self).unwrap_or(0)
+    }
+}
+
+/// A request loaded from history that can be edited and sent again.
+pub struct Composer {
+    source_id: i64,
+    service: String,
+    route_url: String,
+    server_env: Option<String>,
+    original_url: String,
+    pub method: String,
+    pub url: String,
+    /// one `Name: value` per line, sent on top of the client defaults
+    pub headers: String,
+    /// stored headers that were masked or redacted, they can't be sent again as they are
+    left_out: Vec<String>,
+    pub body: String,
+    pub field: ComposerField,
+    pub error: Option<String>,
+}
+
+/// What the composer form turned into, ready to be sent.
+pub struct ComposedRequest {
+    method: Method,
+    url: Url,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    payload: Option<serde_json::Value>,
+    store_args: DbStoreArgs,
+}
+
+/// Stored request headers as `Name: value` lines, and the names of the ones that only have a
+/// masked or redacted value stored. Those are left for the client defaults or the user to fill.
+fn editable_headers(stored: Option<&serde_json::Value>) -> (String, Vec<String>) {
+    let mut lines = Vec::new();
+    let mut left_out = Vec::new();
+
+    let Some(serde_json::Value::Object(map)) = stored else {
+        return (String::new(), left_out);
+    };
+    for (name, values) in map {
+        let values = match values {
+            serde_json::Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        let is_secret = HeaderName::from_bytes(name.as_bytes())
+            .is_ok_and(|name| redactor().is_secret_header(&name));
+        let masked = values
+            .iter()
+            .any(|value| value.as_str().is_none_or(|value| value.contains(REDACTED)));
+        if is_secret || masked {
+            left_out.push(name.clone());
+            continue;
+        }
+        for value in values.iter().filter_map(|value| value.as_str()) {
+            lines.push(format!("{name}: {value}"));
+        }
+    }
+
+    (lines.join("\n"), left_out)
+}
+
+impl Composer {
+    pub fn from_request(
+        request: &ScoobyRequest,
+        request_headers: Option<&serde_json::Value>,
+    ) -> Self {
+        let body = request
+            .payload_json()
+            // GETs are stored with a `null` payload
+            .filter(|payload| !payload.is_null())
+            .and_then(|payload| serde_json::to_string_pretty(payload).ok())
+            .unwrap_or_default();
+        let (headers, left_out) = editable_headers(request_headers);
+
+        Self {
+            source_id: request.id,
+            service: request.service().to_string(),
+            route_url: request.route_url().to_string(),
+            server_env: request.server_env().map(str::to_string),
+            original_url: request.url().to_string(),
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers,
+            left_out,
+            body,
+            field: ComposerField::Method,
+            error: None,
+        }
+    }
+
+    pub fn next_field(&mut self) {
+        let next = (self.field.index() + 1) % ComposerField::ALL.len();
+        self.field = ComposerField::ALL[next];
+    }
+
+    pub fn previous_field(&mut self) {
+        let len = ComposerField::ALL.len();
+        let previous = (self.field.index() + len - 1) % len;
+        self.field = ComposerField::ALL[previous];
+    }
+
+    /// Headers and body span several lines, those are edited in `$EDITOR` instead.
+    pub fn field_is_multiline(&self) -> bool {
+        matches!(self.field, ComposerField::Headers | ComposerField::Body)
+    }
+
+    pub fn multiline_text_mut(&mut self) -> Option<&mut String> {
+        match self.field {
+            ComposerField::Headers => Some(&mut self.headers),
+            ComposerField::Body => Some(&mut self.body),
+            _ => None,
+        }
+    }
+
+    pub fn input_char(&mut self, c: char) {
+        match self.field {
+            ComposerField::Method => self.method.push(c),
+            ComposerField::Url => self.url.push(c),
+            _ => (),
+        }
+    }
+
+    pub fn input_backspace(&mut self) {
+        match self.field {
+            ComposerField::Method => self.method.pop(),
+            ComposerField::Url => self.url.pop(),
+            _ => None,
+        };
+    }
+
+    /// Keeps `route_url` meaningful when the url was edited, falls back to the whole path.
+    fn route_url_for(&self, url: &Url) -> String {
+        if url.as_str() == self.original_url {
+            return self.route_url.clone();
+        }
+
+        let prefix = self
+            .original_url
+            .strip_suffix(&self.route_url)
+            .unwrap_or(&self.original_url);
+        match url.as_str().strip_prefix(prefix) {
+            Some(route) if !prefix.is_empty() => route.to_string(),
+            _ => url.path().trim_start_matches('/').to_string(),
+        }
+    }
+
+    pub fn build(&self) -> Result<ComposedRequest, String> {
+        let method = Method::from_bytes(self.method.trim().to_uppercase().as_bytes())
+            .map_err(|_| format!("invalid method `{}`", self.method))?;
+        let url = Url::parse(self.url.trim()).map_err(|err| format!("invalid url: {err}"))?;
+
+        let mut headers = Vec::new();
+        for line in self.headers.lines().filter(|line| !line.trim().is_empty()) {
+            let Some((name, value)) = line.split_once(':') else {
+                return Err(format!("expected `Name: value`, got `{line}`"));
+            };
+            let name = HeaderName::from_bytes(name.trim().as_bytes())
+                .map_err(|_| format!("invalid header name `{}`", name.trim()))?;
+            let value = HeaderValue::from_str(value.trim())
+                .map_err(|_| format!("invalid value for header `{name}`"))?;
+            headers.push((name, value));
+        }
+
+        let payload = if self.body.trim().is_empty() {
+            None
+        } else {
+            Some(
+                serde_json::from_str::<serde_json::Value>(&self.body)
+                    .map_err(|err| format!("body isn't valid json: {err}"))?,
+            )
+        };
+
+        let store_args = DbStoreArgs {
+            method: method.to_string(),
+            service: self.service.clone(),
+            url: url.to_string(),
+            route_url: self.route_url_for(&url),
+            payload: payload.clone(),
+            attempts: Vec::new(),
+            server_env: self.server_env.clone(),
+            qsp: url.query().map(|query| format!("?{}", query)),
+            request_headers: HeaderMap::new(),
+            tags: Vec::new(),
+        };
+
+        Ok(ComposedRequest {
+            method,
+            url,
+            headers,
+            payload,
+            store_args,
+        })
+    }
+}
+
+/// Sends a composed request the same way `scooby req` does, retries included.
+pub async fn send(
+    client: &Client,
+    retry_policy: &RetryPolicy,
+    request: ComposedRequest,
+) -> Result<(DbStoreArgs, ResponseParts), String> {
+    let mut req_builder = client.request(request.method, request.url);
+    for (name, value) in request.headers {
+        req_builder = req_builder.header(name, value);
+    }
+    if let Some(payload) = &request.payload {
+        req_builder = req_builder.json(payload);
+    }
+    let request_headers = builder_headers(&req_builder);
+
+    let started = Instant::now();
+    let (response, attempts) = send_with_retry(req_builder, retry_policy).await;
+    let response = response.map_err(|err| format!("request errored: {err}"))?;
+    let parts = split_http_response(response, started)
+        .await
+        .map_err(|err| format!("couldn't read response: {err}"))?;
+
+    let store_args = DbStoreArgs {
+        attempts,
+        request_headers,
+        ..request.store_args
+    };
+    Ok((store_args, parts))
+}
+
+/// Centered popup on top of the grid.
+pub struct ComposerView<'a> {
+    pub composer: &'a Composer,
+}
+
+fn field_block(title: String, focused: bool) -> Block<'static> {
+    let block = Block::default().borders(Borders::ALL).title(title);
+    if focused {
+        block.border_style(
+            Style::default()
+                .fg(theme().accent)
+                .add_modifier(Modifier::BOLD),
+        )
+    } else {
+        block
+    }
+}
+
+impl Widget for ComposerView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let composer = self.composer;
+
+        let vertical = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(10),
+                Constraint::Percentage(80),
+                Constraint::Percentage(10),
+            ])
+            .split(area);
+        let popup = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(10),
+                Constraint::Percentage(80),
+                Constraint::Percentage(10),
+            ])
+            .split(vertical[1])[1];
+
+        Clear.render(popup, buf);
+        let outer = Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Compose from #{}", composer.source_id));
+        let inner = outer.inner(popup);
+        outer.render(popup, buf);
+
+        let rows = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3),
+                Constraint::Length(3),
+                Constraint::Length(5),
+                Constraint::Min(3),
+            ])
+            .split(inner);
+
+        let focused = composer.field;
+        let cursor = |field: ComposerField| if focused == field { "▏" } else { "" };
+
+        Paragraph::new(format!(
+            "{}{}",
+            composer.method,
+            cursor(ComposerField::Method)
+        ))
+        .block(field_block(
+            "Method".into(),
+            focused == ComposerField::Method,
+        ))
+        .render(rows[0], buf);
+        Paragraph::new(format!("{}{}", composer.url, cursor(ComposerField::Url)))
+            .block(field_block("Url".into(), focused == ComposerField::Url))
+            .render(rows[1], buf);
+        let headers_title = if composer.left_out.is_empty() {
+            "Headers (Enter: edit in $EDITOR)".to_string()
+        } else {
+            format!(
+                "Headers (Enter: edit in $EDITOR, masked ones left out: {})",
+                composer.left_out.join(", ")
+            )
+        };
+        Paragraph::new(composer.headers.as_str())
+            .block(field_block(
+                headers_title,
+                focused == ComposerField::Headers,
+            ))
+            .render(rows[2], buf);
+        Paragraph::new(composer.body.as_str())
+            .block(field_block(
+                "Body (Enter: edit in $EDITOR)".into(),
+                focused == ComposerField::Body,
+            ))
+            .render(rows[3], buf);
+    }
+}
@@ -0,0 +1,464 @@
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, StatefulWidget, Widget},
+};
+use serde_json::Value;
+
+use super::theme::theme;
+
+/// Containers deeper than this start out collapsed.
+const DEFAULT_OPEN_DEPTH: usize = 2;
+
+/// Roughly what colored_json does for the cli output.
+fn key_style() -> Style {
+    Style::default()
+        .fg(theme().key)
+        .add_modifier(Modifier::BOLD)
+}
+
+fn value_style(value: &Value) -> Style {
+    match value {
+        Value::String(_) => Style::default().fg(theme().string),
+        Value::Number(_) => Style::default().fg(theme().number),
+        Value::Bool(_) => Style::default().fg(theme().boolean),
+        Value::Null => Style::default().fg(theme().muted),
+        _ => Style::default(),
+    }
+}
+
+fn match_style() -> Style {
+    Style::default().bg(theme().match_bg)
+}
+
+/// One rendered row of the tree.
+struct TreeLine {
+    depth: usize,
+    /// json path of the node this row belongs to, `$.items[0].id`
+    path: String,
+    /// the row opens an object/array, so it can be toggled
+    container: bool,
+    /// closing brackets share the path of their opener but are never jumped to
+    closing: bool,
+    spans: Vec<Span<'static>>,
+}
+
+impl TreeLine {
+    fn text(&self) -> String {
+        self.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+}
+
+/// Match paths of the last search, the status line asks for them on every frame.
+struct MatchCache {
+    query: String,
+    /// address of the searched body, trees are reset when the selection changes anyway
+    body: usize,
+    paths: Rc<[String]>,
+}
+
+/// Expanded/collapsed nodes, the cursor and the search of one pane.
+#[derive(Default)]
+pub struct JsonTree {
+    /// only what the user toggled, everything else follows `DEFAULT_OPEN_DEPTH`
+    toggled: HashMap<String, bool>,
+    cursor: usize,
+    pub query: String,
+    matches: RefCell<Option<MatchCache>>,
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Paths use the `--select` syntax so they can be pasted straight into it.
+fn child_path(parent: &str, key: &str) -> String {
+    if is_identifier(key) {
+        format!("{}.{}", parent, key)
+    } else {
+        format!("{}.{}", parent, Value::String(key.to_string()))
+    }
+}
+
+fn count_label(value: &Value) -> String {
+    let (count, noun) = match value {
+        Value::Object(map) => (map.len(), "key"),
+        Value::Array(items) => (items.len(), "item"),
+        _ => return String::new(),
+    };
+    if count == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", count, noun)
+    }
+}
+
+/// Every path a match sits under, so they can be opened when jumping to it.
+fn ancestors(path: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    for (idx, c) in path.char_indices() {
+        if in_quotes {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_quotes = false,
+                _ => (),
+            }
+            continue;
+        }
+        match c {
+            '"' => in_quotes = true,
+            '.' | '[' if idx > 0 => out.push(path[..idx].to_string()),
+            _ => (),
+        }
+    }
+    out
+}
+
+impl JsonTree {
+    fn is_open(&self, path: &str, depth: usize) -> bool {
+        self.toggled
+            .get(path)
+            .copied()
+            .unwrap_or(depth < DEFAULT_OPEN_DEPTH)
+    }
+
+    fn lines(&self, value: &Value) -> Vec<TreeLine> {
+        let mut lines = Vec::new();
+        self.push_lines(&mut lines, value, "$".into(), None, 0, false, false);
+        lines
+    }
+
+    /// Every line as if the whole tree was expanded, used to search collapsed nodes too.
+    fn all_lines(&self, value: &Value) -> Vec<TreeLine> {
+        let mut lines = Vec::new();
+        self.push_lines(&mut lines, value, "$".into(), None, 0, false, true);
+        lines
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn push_lines(
+        &self,
+        lines: &mut Vec<TreeLine>,
+        value: &Value,
+        path: String,
+        key: Option<&str>,
+        depth: usize,
+        comma: bool,
+        expand_all: bool,
+    ) {
+        let mut prefix = Vec::new();
+        if let Some(key) = key {
+            prefix.push(Span::styled(
+                Value::String(key.to_string()).to_string(),
+                key_style(),
+            ));
+            prefix.push(Span::raw(": "));
+        }
+        let comma = if comma { "," } else { "" };
+
+        let (open, close, empty) = match value {
+            Value::Object(map) => ("{", "}", map.is_empty()),
+            Value::Array(items) => ("[", "]", items.is_empty()),
+            leaf => {
+                let mut spans = vec![Span::raw("  ")];
+                spans.extend(prefix);
+                spans.push(Span::styled(leaf.to_string(), value_style(leaf)));
+                spans.push(Span::raw(comma));
+                lines.push(TreeLine {
+                    depth,
+                    path,
+                    container: false,
+                    closing: false,
+                    spans,
+                });
+                return;
+            }
+        };
+
+        if empty {
+            let mut spans = vec![Span::raw("  ")];
+            spans.extend(prefix);
+            spans.push(Span::raw(format!("{}{}{}", open, close, comma)));
+            lines.push(TreeLine {
+                depth,
+                path,
+                container: false,
+                closing: false,
+                spans,
+            });
+            return;
+        }
+
+        if !expand_all && !self.is_open(&path, depth) {
+            let mut spans = vec![Span::raw("▸ ")];
+            spans.extend(prefix);
+            spans.push(Span::raw(format!("{}…{}{}  ", open, close, comma)));
+            spans.push(Span::styled(
+                count_label(value),
+                Style::default()
+                    .fg(theme().muted)
+                    .add_modifier(Modifier::ITALIC),
+            ));
+            lines.push(TreeLine {
+                depth,
+                path,
+                container: true,
+                closing: false,
+                spans,
+            });
+            return;
+        }
+
+        let mut spans = vec![Span::raw("▾ ")];
+        spans.extend(prefix);
+        spans.push(Span::raw(open));
+        lines.push(TreeLine {
+            depth,
+            path: path.clone(),
+            container: true,
+            closing: false,
+            spans,
+        });
+        match value {
+            Value::Object(map) => {
+                for (idx, (key, child)) in map.iter().enumerate() {
+                    let child_path = child_path(&path, key);
+                    let comma = idx + 1 < map.len();
+                    self.push_lines(
+                        lines,
+                        child,
+                        child_path,
+                        Some(key),
+                        depth + 1,
+                        comma,
+                        expand_all,
+                    );
+                }
+            }
+            Value::Array(items) => {
+                for (idx, child) in items.iter().enumerate() {
+                    let child_path = format!("{}[{}]", path, idx);
+                    let comma = idx + 1 < items.len();
+                    self.push_lines(lines, child, child_path, None, depth + 1, comma, expand_all);
+                }
+            }
+            _ => (),
+        }
+        lines.push(TreeLine {
+            depth,
+            path,
+            container: false,
+            closing: true,
+            spans: vec![Span::raw(format!("{}{}", close, comma))],
+        });
+    }
+
+    pub fn move_cursor(&mut self, value: Option<&Value>, delta: isize) {
+        let len = value.map(|value| self.lines(value).len()).unwrap_or(0);
+        if len == 0 {
+            self.cursor = 0;
+            return;
+        }
+        self.cursor = self.cursor.saturating_add_signed(delta).min(len - 1);
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Puts the cursor on a row, e.g. the one that was clicked.
+    pub fn set_cursor(&mut self, value: Option<&Value>, row: usize) {
+        let len = value.map(|value| self.lines(value).len()).unwrap_or(0);
+        if row < len {
+            self.cursor = row;
+        }
+    }
+
+    /// Expands or collapses the node under the cursor, `None` flips it.
+    pub fn set_open(&mut self, value: Option<&Value>, open: Option<bool>) {
+        let Some(value) = value else {
+            return;
+        };
+        let lines = self.lines(value);
+        let Some(line) = lines.get(self.cursor) else {
+            return;
+        };
+        if !line.container {
+            return;
+        }
+
+        let is_open = self.is_open(&line.path, line.depth);
+        let open = open.unwrap_or(!is_open);
+        if open != is_open {
+            self.toggled.insert(line.path.clone(), open);
+        }
+    }
+
+    pub fn current_path(&self, value: Option<&Value>) -> Option<String> {
+        let lines = self.lines(value?);
+        lines.get(self.cursor).map(|line| line.path.clone())
+    }
+
+    fn is_match(&self, line: &TreeLine) -> bool {
+        !self.query.is_empty()
+            && !line.closing
+            && line
+                .text()
+                .to_lowercase()
+                .contains(&self.query.to_lowercase())
+    }
+
+    /// Paths of every matching node, in document order and including collapsed ones. Only
+    /// walks the whole document again when the query or the body changed.
+    fn match_paths(&self, value: &Value) -> Rc<[String]> {
+        let body = value as *const Value as usize;
+        let mut cache = self.matches.borrow_mut();
+        if let Some(cached) = cache.as_ref()
+            && cached.body == body
+            && cached.query == self.query
+        {
+            return cached.paths.clone();
+        }
+
+        let paths: Rc<[String]> = self
+            .all_lines(value)
+            .into_iter()
+            .filter(|line| self.is_match(line))
+            .map(|line| line.path)
+            .collect();
+        *cache = Some(MatchCache {
+            query: self.query.clone(),
+            body,
+            paths: paths.clone(),
+        });
+        paths
+    }
+
+    /// `(current, total)` for the status line, current is 0 when the cursor isn't on a match.
+    pub fn match_position(&self, value: Option<&Value>) -> (usize, usize) {
+        let Some(value) = value else {
+            return (0, 0);
+        };
+        if self.query.is_empty() {
+            return (0, 0);
+        }
+        let matches = self.match_paths(value);
+        let current = self
+            .current_path(Some(value))
+            .and_then(|path| matches.iter().position(|m| *m == path))
+            .map(|idx| idx + 1)
+            .unwrap_or(0);
+        (current, matches.len())
+    }
+
+    /// Moves the cursor to the next (or previous) match, opening whatever it's nested in.
+    /// `stay` keeps the cursor where it is if it's already on a match, for incremental search.
+    pub fn jump_to_match(&mut self, value: Option<&Value>, forward: bool, stay: bool) {
+        let Some(value) = value else {
+            return;
+        };
+        if self.query.is_empty() {
+            return;
+        }
+        let all = self.all_lines(value);
+        if !all.iter().any(|line| self.is_match(line)) {
+            return;
+        }
+
+        // where the cursor is in the fully expanded document
+        let current = self
+            .current_path(Some(value))
+            .and_then(|path| {
+                all.iter()
+                    .position(|line| !line.closing && line.path == path)
+            })
+            .unwrap_or(0);
+
+        let len = all.len();
+        let candidates: Vec<usize> = if forward {
+            let start = if stay { current } else { current + 1 };
+            (start..len).chain(0..start).collect()
+        } else {
+            let start = if stay { current + 1 } else { current };
+            (0..start).rev().chain((start..len).rev()).collect()
+        };
+        let Some(target) = candidates
+            .into_iter()
+            .find(|idx| self.is_match(&all[*idx]))
+            .map(|idx| all[idx].path.clone())
+        else {
+            return;
+        };
+
+        for ancestor in ancestors(&target) {
+            self.toggled.insert(ancestor, true);
+        }
+        if let Some(idx) = self
+            .lines(value)
+            .iter()
+            .position(|line| !line.closing && line.path == target)
+        {
+            self.cursor = idx;
+        }
+    }
+}
+
+/// Renders a tree, or a placeholder when there's nothing to show.
+pub struct JsonTreeView<'a> {
+    pub value: Result<&'a Value, &'a str>,
+    pub tree: &'a JsonTree,
+    pub block: Block<'a>,
+    /// the cursor is only shown in the focused pane
+    pub focused: bool,
+}
+
+impl Widget for JsonTreeView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let value = match self.value {
+            Ok(value) => value,
+            Err(placeholder) => {
+                Paragraph::new(placeholder)
+                    .block(self.block)
+                    .render(area, buf);
+                return;
+            }
+        };
+
+        let items: Vec<ListItem> = self
+            .tree
+            .lines(value)
+            .into_iter()
+            .map(|line| {
+                let style = if self.tree.is_match(&line) {
+                    match_style()
+                } else {
+                    Style::default()
+                };
+                let mut spans = vec![Span::raw("  ".repeat(line.depth))];
+                spans.extend(line.spans);
+                ListItem::new(Line::from(spans)).style(style)
+            })
+            .collect();
+
+        let list = List::new(items)
+            .block(self.block)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = ListState::default();
+        if self.focused {
+            state.select(Some(self.tree.cursor));
+        }
+        StatefulWidget::render(list, area, buf, &mut state);
+    }
+}
@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    buffer::Buffer,
+    crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+    layout::{Constraint, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Clear, Row, Table, Widget},
+};
+use serde::Deserialize;
+
+use super::theme::theme;
+
+/// What a key does on the requests tab. Text input, the composer and the sql tab keep their
+/// fixed keys since those are typed into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Down,
+    Up,
+    PageDown,
+    PageUp,
+    Top,
+    Bottom,
+    Fullscreen,
+    NextPane,
+    Search,
+    Filter,
+    Compose,
+    Follow,
+    Metadata,
+    Pin,
+    Note,
+    Delete,
+    Collapse,
+    Expand,
+    Toggle,
+    CopyPath,
+    NextMatch,
+    PreviousMatch,
+    ResizeLeft,
+    ResizeRight,
+    ResizeUp,
+    ResizeDown,
+    Help,
+}
+
+impl Action {
+    /// In the order they're listed in the help overlay.
+    const ALL: [Action; 28] = [
+        Action::Down,
+        Action::Up,
+        Action::PageDown,
+        Action::PageUp,
+        Action::Top,
+        Action::Bottom,
+        Action::Fullscreen,
+        Action::NextPane,
+        Action::Collapse,
+        Action::Expand,
+        Action::Toggle,
+        Action::CopyPath,
+        Action::Search,
+        Action::NextMatch,
+        Action::PreviousMatch,
+        Action::Filter,
+        Action::Compose,
+        Action::Follow,
+        Action::Metadata,
+        Action::Pin,
+        Action::Note,
+        Action::Delete,
+        Action::ResizeLeft,
+        Action::ResizeRight,
+        Action::ResizeUp,
+        Action::ResizeDown,
+        Action::Help,
+        Action::Quit,
+    ];
+
+    /// Key in `[ui.keys]`.
+    fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::Top => "top",
+            Action::Bottom => "bottom",
+            Action::Fullscreen => "fullscreen",
+            Action::NextPane => "next_pane",
+            Action::Search => "search",
+            Action::Filter => "filter",
+            Action::Compose => "compose",
+            Action::Follow => "follow",
+            Action::Metadata => "metadata",
+            Action::Pin => "pin",
+            Action::Note => "note",
+            Action::Delete => "delete",
+            Action::Collapse => "collapse",
+            Action::Expand => "expand",
+            Action::Toggle => "toggle",
+            Action::CopyPath => "copy_path",
+            Action::NextMatch => "next_match",
+            Action::PreviousMatch => "previous_match",
+            Action::ResizeLeft => "resize_left",
+            Action::ResizeRight => "resize_right",
+            Action::ResizeUp => "resize_up",
+            Action::ResizeDown => "resize_down",
+            Action::Help => "help",
+        }
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Down => "next request / tree row",
+            Action::Up => "previous request / tree row",
+            Action::PageDown => "10 rows down",
+            Action::PageUp => "10 rows up",
+            Action::Top => "first row",
+            Action::Bottom => "last loaded row",
+            Action::Fullscreen => "toggle fullscreen",
+            Action::NextPane => "next pane in fullscreen, sql tab otherwise",
+            Action::Search => "search the list, or the pane in fullscreen",
+            Action::Filter => "filter the list",
+            Action::Compose => "edit and resend the request",
+            Action::Follow => "follow new requests",
+            Action::Metadata => "show/hide the metadata pane",
+            Action::Pin => "pin/unpin the request",
+            Action::Note => "edit the note, empty removes it",
+            Action::Delete => "delete the request",
+            Action::Collapse => "collapse node",
+            Action::Expand => "expand node",
+            Action::Toggle => "toggle node",
+            Action::CopyPath => "copy json path",
+            Action::NextMatch => "next match",
+            Action::PreviousMatch => "previous match",
+            Action::ResizeLeft => "move the pane border left",
+            Action::ResizeRight => "move the pane border right",
+            Action::ResizeUp => "move the border under payload up",
+            Action::ResizeDown => "move the border under payload down",
+            Action::Help => "this help",
+        }
+    }
+
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Down => &["j", "Down"],
+            Action::Up => &["k", "Up"],
+            Action::PageDown => &["Ctrl+d", "PageDown"],
+            Action::PageUp => &["Ctrl+u", "PageUp"],
+            Action::Top => &["Home"],
+            Action::Bottom => &["End"],
+            Action::Fullscreen => &["Enter"],
+            Action::NextPane => &["Tab"],
+            Action::Search => &["/"],
+            Action::Filter => &["f"],
+            Action::Compose => &["e"],
+            Action::Follow => &["F"],
+            Action::Metadata => &["m"],
+            Action::Pin => &["p"],
+            Action::Note => &["a"],
+            Action::Delete => &["D"],
+            Action::Collapse => &["h", "Left"],
+            Action::Expand => &["l", "Right"],
+            Action::Toggle => &["Space"],
+            Action::CopyPath => &["y"],
+            Action::NextMatch => &["n"],
+            Action::PreviousMatch => &["N"],
+            Action::ResizeLeft => &["<"],
+            Action::ResizeRight => &[">"],
+            Action::ResizeUp => &["-"],
+            Action::ResizeDown => &["+"],
+            Action::Help => &["?"],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct KeyBinding {
+    code: KeyCode,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl KeyBinding {
+    /// `q`, `Ctrl+d`, `Alt+Enter`, `PageDown`, `F5`... Case matters for single characters.
+    fn parse(text: &str) -> Result<KeyBinding, String> {
+        let mut ctrl = false;
+        let mut alt = false;
+        let mut rest = text;
+        loop {
+            let lower = rest.to_lowercase();
+            if lower.starts_with("ctrl+") && rest.len() > 5 {
+                ctrl = true;
+                rest = &rest[5..];
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                alt = true;
+                rest = &rest[4..];
+            } else {
+                break;
+            }
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "enter" => KeyCode::Enter,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "esc" => KeyCode::Esc,
+                "space" => KeyCode::Char(' '),
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                other => match other.strip_prefix('f').map(str::parse::<u8>) {
+                    Some(Ok(n)) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key `{}`", text)),
+                },
+            },
+        };
+        Ok(KeyBinding { code, ctrl, alt })
+    }
+
+    fn matches(&self, key: &KeyEvent) -> bool {
+        // shift is already part of the character for letters and symbols
+        self.code == key.code
+            && self.ctrl == key.modifiers.contains(KeyModifiers::CONTROL)
+            && self.alt == key.modifiers.contains(KeyModifiers::ALT)
+    }
+
+    fn label(&self) -> String {
+        let key = match self.code {
+            KeyCode::Char(' ') => "Space".to_string(),
+            KeyCode::Char(c) => c.to_string(),
+            KeyCode::F(n) => format!("F{}", n),
+            KeyCode::PageUp => "PageUp".into(),
+            KeyCode::PageDown => "PageDown".into(),
+            KeyCode::Up => "↑".into(),
+            KeyCode::Down => "↓".into(),
+            KeyCode::Left => "←".into(),
+            KeyCode::Right => "→".into(),
+            other => format!("{:?}", other),
+        };
+        match (self.ctrl, self.alt) {
+            (true, true) => format!("Ctrl+Alt+{}", key),
+            (true, false) => format!("Ctrl+{}", key),
+            (false, true) => format!("Alt+{}", key),
+            (false, false) => key,
+        }
+    }
+}
+
+/// One key or a list of them, `quit = "x"` and `down = ["j", "Down"]` both work.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Bindings of every action on the requests tab.
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Keymap {
+    /// Defaults, with every action listed in `[ui.keys]` replaced by what's configured there.
+    pub fn from_config(config: HashMap<String, KeyList>) -> Result<Keymap, String> {
+        if let Some(unknown) = config
+            .keys()
+            .find(|name| !Action::ALL.iter().any(|action| action.name() == *name))
+        {
+            return Err(format!("unknown action `{}`", unknown));
+        }
+
+        let mut bindings = Vec::new();
+        for action in Action::ALL {
+            let keys = match config.get(action.name()) {
+                Some(KeyList::One(key)) => vec![key.clone()],
+                Some(KeyList::Many(keys)) => keys.clone(),
+                None => action
+                    .default_keys()
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect(),
+            };
+            let keys = keys
+                .iter()
+                .map(|key| KeyBinding::parse(key))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| format!("{} for {}", err, action.name()))?;
+            bindings.push((action, keys));
+        }
+        Ok(Keymap { bindings })
+    }
+
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, keys)| keys.iter().any(|binding| binding.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    /// `j/↓` style, for hints in the status line.
+    pub fn label(&self, action: Action) -> String {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map(|(_, keys)| {
+                keys.iter()
+                    .map(KeyBinding::label)
+                    .collect::<Vec<_>>()
+                    .join("/")
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// `?` overlay listing the active bindings.
+pub struct HelpView<'a> {
+    pub keymap: &'a Keymap,
+}
+
+impl Widget for HelpView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let width = area.width.min(72);
+        let height = area.height.min(Action::ALL.len() as u16 + 3);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let rows = self.keymap.bindings.iter().map(|(action, _)| {
+            Row::new([self.keymap.label(*action), action.description().to_string()])
+        });
+        let table = Table::new(rows, [Constraint::Length(20), Constraint::Min(0)])
+            .header(
+                Row::new(["key", "action"]).style(
+                    Style::default()
+                        .fg(theme().key)
+                        .add_modifier(Modifier::BOLD),
+                ),
+            )
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title("Keys (any key closes)")
+                    .border_style(Style::default().fg(theme().accent)),
+            )
+            .column_spacing(2);
+
+        Clear.render(popup, buf);
+        Widget::render(table, popup, buf);
+    }
+}
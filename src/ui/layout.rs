@@ -0,0 +1,180 @@
+use ratatui::layout::{Constraint, Direction, Layout, Position, Rect};
+
+/// Panes can't be squashed smaller than this share of the screen
+const MIN_SHARE: u16 = 15;
+const MAX_SHARE: u16 = 85;
+/// How far the resize keys move a border, in percent
+pub const RESIZE_STEP: i16 = 5;
+
+/// Where the pane borders are, remembered between sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaneLayout {
+    /// width of the request list in percent, the detail panes get the rest
+    pub list_width: u16,
+    /// payload's share of the detail panes in percent, response and metadata split the rest
+    pub payload_share: u16,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        PaneLayout {
+            list_width: 50,
+            payload_share: 50,
+        }
+    }
+}
+
+/// A border that can be dragged with the mouse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Border {
+    /// between the list and the detail panes
+    List,
+    /// between payload and response
+    Payload,
+}
+
+/// Where everything ended up in the last frame, used to figure out what the mouse points at.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PaneAreas {
+    /// everything but the status bar
+    pub grid: Rect,
+    /// `None` in fullscreen
+    pub list: Option<Rect>,
+    pub payload: Rect,
+    pub response: Rect,
+    pub metadata: Option<Rect>,
+}
+
+fn clamp_share(share: i32) -> u16 {
+    share.clamp(MIN_SHARE as i32, MAX_SHARE as i32) as u16
+}
+
+/// How far `pos` is into a span starting at `start`, in percent.
+fn percent_of(pos: u16, start: u16, len: u16) -> i32 {
+    if len == 0 {
+        return 50;
+    }
+    (pos.saturating_sub(start) as i32 * 100) / len as i32
+}
+
+impl PaneLayout {
+    pub const LIST_WIDTH_KEY: &str = "layout.list_width";
+    pub const PAYLOAD_SHARE_KEY: &str = "layout.payload_share";
+
+    /// Moves the border between the list and the details, or payload and response in fullscreen.
+    pub fn resize(&mut self, border: Border, delta: i16) {
+        match border {
+            Border::List => {
+                self.list_width = clamp_share(self.list_width as i32 + delta as i32);
+            }
+            Border::Payload => {
+                self.payload_share = clamp_share(self.payload_share as i32 + delta as i32);
+            }
+        }
+    }
+
+    /// Sets a value read back from the db, anything unparsable keeps the default.
+    pub fn load(&mut self, key: &str, value: &str) {
+        let Ok(share) = value.parse::<i32>() else {
+            return;
+        };
+        match key {
+            Self::LIST_WIDTH_KEY => self.list_width = clamp_share(share),
+            Self::PAYLOAD_SHARE_KEY => self.payload_share = clamp_share(share),
+            _ => (),
+        }
+    }
+
+    pub fn areas(&self, grid: Rect, fullscreen: bool, show_metadata: bool) -> PaneAreas {
+        let payload = Constraint::Percentage(self.payload_share);
+        let detail_constraints = if show_metadata {
+            vec![payload, Constraint::Fill(1), Constraint::Fill(1)]
+        } else {
+            vec![payload, Constraint::Fill(1)]
+        };
+
+        let (list, details, direction) = if fullscreen {
+            (None, grid, Direction::Horizontal)
+        } else {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(self.list_width), Constraint::Fill(1)])
+                .split(grid);
+            (Some(columns[0]), columns[1], Direction::Vertical)
+        };
+
+        let panes = Layout::default()
+            .direction(direction)
+            .constraints(detail_constraints)
+            .split(details);
+        PaneAreas {
+            grid,
+            list,
+            payload: panes[0],
+            response: panes[1],
+            metadata: panes.get(2).copied(),
+        }
+    }
+
+    /// The border under the mouse, either of the two border cells counts.
+    pub fn border_at(areas: &PaneAreas, pos: Position) -> Option<Border> {
+        if !areas.grid.contains(pos) {
+            return None;
+        }
+        if let Some(list) = areas.list
+            && (pos.x + 1 == list.right() || pos.x == areas.payload.x)
+        {
+            return Some(Border::List);
+        }
+
+        let payload = areas.payload;
+        let on_payload_border = if areas.list.is_some() {
+            // stacked on top of each other
+            payload.contains(Position::new(pos.x, payload.y))
+                && (pos.y + 1 == payload.bottom() || pos.y == areas.response.y)
+        } else {
+            pos.x + 1 == payload.right() || pos.x == areas.response.x
+        };
+        on_payload_border.then_some(Border::Payload)
+    }
+
+    /// Follows a dragged border to the mouse.
+    pub fn drag(&mut self, border: Border, areas: &PaneAreas, pos: Position) {
+        let grid = areas.grid;
+        match border {
+            Border::List => {
+                self.list_width = clamp_share(percent_of(pos.x, grid.x, grid.width));
+            }
+            Border::Payload if areas.list.is_some() => {
+                self.payload_share = clamp_share(percent_of(pos.y, grid.y, grid.height));
+            }
+            Border::Payload => {
+                self.payload_share = clamp_share(percent_of(pos.x, grid.x, grid.width));
+            }
+        }
+    }
+}
+
+/// First visible row of a bordered list in `area` with `selected` kept in view. The list state
+/// isn't kept between frames, so this is the same thing ratatui works out from a fresh one.
+pub fn list_offset(selected: Option<usize>, area: Rect) -> usize {
+    let height = area.height.saturating_sub(2) as usize;
+    match selected {
+        Some(selected) if height > 0 => selected.saturating_sub(height - 1),
+        _ => 0,
+    }
+}
+
+/// Row under the mouse inside a bordered list, `None` on the borders.
+pub fn list_row(selected: Option<usize>, area: Rect, pos: Position) -> Option<usize> {
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+    if !inner.contains(pos) {
+        return None;
+    }
+    Some(list_offset(selected, area) + (pos.y - inner.y) as usize)
+}
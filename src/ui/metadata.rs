@@ -0,0 +1,136 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Paragraph, Widget},
+};
+use serde_json::Value;
+
+use crate::db::{RequestMetadata, made_by};
+
+use super::theme::theme;
+
+fn label_style() -> Style {
+    Style::default().fg(theme().accent)
+}
+
+fn section_style() -> Style {
+    Style::default()
+        .fg(theme().key)
+        .add_modifier(Modifier::BOLD)
+}
+
+fn missing() -> Span<'static> {
+    Span::styled("not recorded", Style::default().fg(theme().muted))
+}
+
+fn status_style(status: u16) -> Style {
+    let color = match status {
+        200..=399 => theme().success,
+        400..=499 => theme().warning,
+        _ => theme().danger,
+    };
+    Style::default().fg(color).add_modifier(Modifier::BOLD)
+}
+
+fn field(label: &'static str, value: Span<'static>) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(format!("{:<9}", label), label_style()),
+        value,
+    ])
+}
+
+fn optional(value: Option<&str>) -> Span<'static> {
+    match value {
+        Some(value) if !value.is_empty() => Span::raw(value.to_string()),
+        _ => missing(),
+    }
+}
+
+/// `name: value` per header, repeated headers are stored as arrays and get a line each.
+fn header_lines(title: &'static str, headers: Option<&Value>) -> Vec<Line<'static>> {
+    let mut lines = vec![Line::raw(""), Line::styled(title, section_style())];
+    let Some(Value::Object(map)) = headers else {
+        lines.push(Line::from(vec![Span::raw("  "), missing()]));
+        return lines;
+    };
+    if map.is_empty() {
+        lines.push(Line::styled("  none", Style::default().fg(theme().muted)));
+    }
+    for (name, value) in map {
+        let values = match value {
+            Value::Array(values) => values.iter().collect(),
+            value => vec![value],
+        };
+        for value in values {
+            let value = value
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or(value.to_string());
+            lines.push(Line::from(vec![
+                Span::raw("  "),
+                Span::styled(format!("{}: ", name), label_style()),
+                Span::raw(value),
+            ]));
+        }
+    }
+    lines
+}
+
+pub fn metadata_lines(metadata: &RequestMetadata) -> Vec<Line<'static>> {
+    let status = match metadata.status {
+        Some(status) => Span::styled(status.to_string(), status_style(status)),
+        None => missing(),
+    };
+    let timing = match metadata.elapsed_ms {
+        Some(elapsed_ms) if metadata.attempts > 1 => Span::raw(format!(
+            "{}ms over {} attempts",
+            elapsed_ms, metadata.attempts
+        )),
+        Some(elapsed_ms) => Span::raw(format!("{}ms", elapsed_ms)),
+        None => missing(),
+    };
+
+    let mut lines = vec![
+        field("url", Span::raw(metadata.full_url.clone())),
+        field("query", optional(metadata.qsp.as_deref())),
+        field("env", optional(metadata.server_env.as_deref())),
+        field("status", status),
+        field("time", timing),
+        field(
+            "sent",
+            Span::raw(metadata.created_at.format("%Y-%m-%d %H:%M:%S").to_string()),
+        ),
+        field(
+            "by",
+            optional(made_by(metadata.user.as_deref(), metadata.host.as_deref()).as_deref()),
+        ),
+    ];
+    lines.extend(header_lines(
+        "Request headers",
+        metadata.request_headers.as_ref(),
+    ));
+    lines.extend(header_lines(
+        "Response headers",
+        metadata.response_headers.as_ref(),
+    ));
+    lines
+}
+
+/// Urls, headers and timing of the selected request, scrolled with j/k when focused.
+pub struct MetadataView<'a> {
+    pub metadata: Result<&'a RequestMetadata, &'a str>,
+    pub block: Block<'a>,
+    pub scroll: u16,
+}
+
+impl Widget for MetadataView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let paragraph = match self.metadata {
+            Ok(metadata) => Paragraph::new(metadata_lines(metadata)).scroll((self.scroll, 0)),
+            Err(placeholder) => Paragraph::new(placeholder),
+        };
+        paragraph.block(self.block).render(area, buf);
+    }
+}
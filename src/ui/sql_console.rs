@@ -0,0 +1,220 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, StatefulWidget, Table, TableState, Widget},
+};
+
+use crate::db::SqlOutput;
+
+use super::theme::theme;
+
+/// Results past this are dropped, the console is for poking around and not for exports.
+pub const MAX_ROWS: usize = 1000;
+/// How many past queries are loaded when the console is opened
+pub const HISTORY_SIZE: i64 = 200;
+/// Wider columns are cut off, `Left`/`Right` scroll through columns instead
+const MAX_COLUMN_WIDTH: usize = 40;
+
+/// State of the sql tab.
+#[derive(Default)]
+pub struct SqlConsole {
+    pub input: String,
+    pub history: Vec<String>,
+    pub history_loaded: bool,
+    /// position while going through history with up/down, `None` when editing a new query
+    history_pos: Option<usize>,
+    pub output: Option<SqlOutput>,
+    pub error: Option<String>,
+    pub elapsed_ms: u128,
+    pub writes_enabled: bool,
+    row: usize,
+    column_offset: usize,
+}
+
+impl SqlConsole {
+    pub fn input_char(&mut self, c: char) {
+        self.history_pos = None;
+        self.input.push(c);
+    }
+
+    pub fn input_backspace(&mut self) {
+        self.history_pos = None;
+        self.input.pop();
+    }
+
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let pos = match self.history_pos {
+            Some(pos) => pos.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+        self.history_pos = Some(pos);
+        self.input = self.history[pos].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        let Some(pos) = self.history_pos else {
+            return;
+        };
+        if pos + 1 < self.history.len() {
+            self.history_pos = Some(pos + 1);
+            self.input = self.history[pos + 1].clone();
+        } else {
+            // past the newest entry, back to an empty line like shells do
+            self.history_pos = None;
+            self.input.clear();
+        }
+    }
+
+    pub fn remember(&mut self, query: &str) {
+        self.history_pos = None;
+        if self.history.last().map(String::as_str) != Some(query) {
+            self.history.push(query.to_string());
+        }
+    }
+
+    pub fn show(&mut self, output: Result<SqlOutput, String>, elapsed_ms: u128) {
+        self.row = 0;
+        self.column_offset = 0;
+        self.elapsed_ms = elapsed_ms;
+        match output {
+            Ok(output) => {
+                self.output = Some(output);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err),
+        }
+    }
+
+    pub fn scroll_rows(&mut self, delta: isize) {
+        let len = self.output.as_ref().map(|o| o.rows.len()).unwrap_or(0);
+        self.row = self
+            .row
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    pub fn scroll_columns(&mut self, delta: isize) {
+        let len = self.output.as_ref().map(|o| o.columns.len()).unwrap_or(0);
+        self.column_offset = self
+            .column_offset
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1));
+    }
+
+    pub fn status_line(&self) -> String {
+        if let Some(err) = &self.error {
+            return format!("error: {}", err);
+        }
+        let summary = match &self.output {
+            Some(SqlOutput {
+                affected: Some(affected),
+                ..
+            }) => format!("{} rows changed in {}ms", affected, self.elapsed_ms),
+            Some(output) => format!(
+                "{} rows{} in {}ms",
+                output.rows.len(),
+                if output.truncated {
+                    format!(" (first {})", MAX_ROWS)
+                } else {
+                    String::new()
+                },
+                self.elapsed_ms
+            ),
+            None => String::new(),
+        };
+        format!(
+            "{}  Enter run  ↑/↓ history  Ctrl+u/d ←/→ scroll  Ctrl+w writes  Esc back",
+            summary
+        )
+    }
+}
+
+pub struct SqlConsoleView<'a> {
+    pub console: &'a SqlConsole,
+}
+
+impl Widget for SqlConsoleView<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let console = self.console;
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area);
+
+        let (title, border) = if console.writes_enabled {
+            ("SQL (writes enabled)", Style::default().fg(theme().danger))
+        } else {
+            ("SQL (read-only)", Style::default().fg(theme().accent))
+        };
+        Paragraph::new(format!("{}▏", console.input))
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(title)
+                    .border_style(border.add_modifier(Modifier::BOLD)),
+            )
+            .render(layout[0], buf);
+
+        let results_block = Block::default().borders(Borders::ALL).title("Results");
+        let Some(output) = &console.output else {
+            Paragraph::new("Type a query and press Enter, e.g. SELECT * FROM requests LIMIT 10")
+                .block(results_block)
+                .render(layout[1], buf);
+            return;
+        };
+        if output.columns.is_empty() {
+            Paragraph::new(format!(
+                "{} rows changed",
+                output.affected.unwrap_or_default()
+            ))
+            .block(results_block)
+            .render(layout[1], buf);
+            return;
+        }
+
+        let offset = console.column_offset;
+        let widths: Vec<Constraint> = output
+            .columns
+            .iter()
+            .enumerate()
+            .skip(offset)
+            .map(|(idx, column)| {
+                let widest = output
+                    .rows
+                    .iter()
+                    .filter_map(|row| row.get(idx))
+                    .map(|value| value.chars().count())
+                    .chain([column.chars().count()])
+                    .max()
+                    .unwrap_or(0);
+                Constraint::Length(widest.min(MAX_COLUMN_WIDTH) as u16)
+            })
+            .collect();
+
+        let header = Row::new(output.columns.iter().skip(offset).cloned()).style(
+            Style::default()
+                .fg(theme().key)
+                .add_modifier(Modifier::BOLD),
+        );
+        let rows = output.rows.iter().map(|row| {
+            // keep cells on one line, the table doesn't wrap
+            Row::new(
+                row.iter()
+                    .skip(offset)
+                    .map(|value| value.replace('\n', " ")),
+            )
+        });
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(results_block)
+            .column_spacing(2)
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        let mut state = TableState::default().with_selected(Some(console.row));
+        StatefulWidget::render(table, layout[1], buf, &mut state);
+    }
+}
@@ -0,0 +1,118 @@
+use std::{str::FromStr, sync::OnceLock};
+
+use ratatui::style::Color;
+use serde::Deserialize;
+
+/// `[ui.theme]` in the config file. `preset` picks the base colours, every other field overrides
+/// one of them. Colours are names (`red`, `lightblue`), `#rrggbb` or an ANSI index.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ThemeConfig {
+    pub preset: Option<String>,
+    pub accent: Option<String>,
+    pub highlight: Option<String>,
+    pub new: Option<String>,
+    pub muted: Option<String>,
+    pub key: Option<String>,
+    pub string: Option<String>,
+    pub number: Option<String>,
+    pub boolean: Option<String>,
+    pub match_bg: Option<String>,
+    pub success: Option<String>,
+    pub warning: Option<String>,
+    pub danger: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    /// focused pane borders, labels
+    pub accent: Color,
+    /// selected row, tabs and the status bar while typing
+    pub highlight: Color,
+    /// requests stored since the ui was opened
+    pub new: Color,
+    /// status bar, placeholders and json nulls
+    pub muted: Color,
+    /// json keys and table headers
+    pub key: Color,
+    pub string: Color,
+    pub number: Color,
+    pub boolean: Color,
+    /// background of search matches
+    pub match_bg: Color,
+    pub success: Color,
+    pub warning: Color,
+    /// errors, 5xx and the sql console with writes enabled
+    pub danger: Color,
+}
+
+impl Theme {
+    pub const DARK: Theme = Theme {
+        accent: Color::Cyan,
+        highlight: Color::Yellow,
+        new: Color::Green,
+        muted: Color::DarkGray,
+        key: Color::Blue,
+        string: Color::Green,
+        number: Color::Magenta,
+        boolean: Color::Yellow,
+        match_bg: Color::DarkGray,
+        success: Color::Green,
+        warning: Color::Yellow,
+        danger: Color::Red,
+    };
+
+    /// Yellow text and gray backgrounds disappear on white terminals.
+    pub const LIGHT: Theme = Theme {
+        accent: Color::Blue,
+        highlight: Color::Blue,
+        new: Color::Green,
+        muted: Color::DarkGray,
+        key: Color::Blue,
+        string: Color::Green,
+        number: Color::Magenta,
+        boolean: Color::Red,
+        match_bg: Color::LightYellow,
+        success: Color::Green,
+        warning: Color::Magenta,
+        danger: Color::Red,
+    };
+
+    pub fn from_config(config: ThemeConfig) -> Result<Theme, String> {
+        let base = match config.preset.as_deref() {
+            None | Some("dark") => Theme::DARK,
+            Some("light") => Theme::LIGHT,
+            Some(other) => return Err(format!("unknown preset `{}`, use dark or light", other)),
+        };
+
+        let color = |name: &str, value: Option<String>, default: Color| match value {
+            Some(value) => Color::from_str(&value)
+                .map_err(|_| format!("{} = \"{}\" isn't a colour", name, value)),
+            None => Ok(default),
+        };
+        Ok(Theme {
+            accent: color("accent", config.accent, base.accent)?,
+            highlight: color("highlight", config.highlight, base.highlight)?,
+            new: color("new", config.new, base.new)?,
+            muted: color("muted", config.muted, base.muted)?,
+            key: color("key", config.key, base.key)?,
+            string: color("string", config.string, base.string)?,
+            number: color("number", config.number, base.number)?,
+            boolean: color("boolean", config.boolean, base.boolean)?,
+            match_bg: color("match_bg", config.match_bg, base.match_bg)?,
+            success: color("success", config.success, base.success)?,
+            warning: color("warning", config.warning, base.warning)?,
+            danger: color("danger", config.danger, base.danger)?,
+        })
+    }
+}
+
+static THEME: OnceLock<Theme> = OnceLock::new();
+
+/// Set once when the ui starts, the widgets read it while rendering.
+pub fn set_theme(theme: Theme) {
+    let _ = THEME.set(theme);
+}
+
+pub fn theme() -> &'static Theme {
+    THEME.get().unwrap_or(&Theme::DARK)
+}
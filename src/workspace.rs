@@ -0,0 +1,108 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::cfg::Cfg;
+
+const SCOOBY_DIR: &str = ".scooby";
+const DEFAULT_DB: &str = "dooby.db";
+const WORKSPACES_DIR: &str = "workspaces";
+const DB_ENV: &str = "SCOOBY_DB";
+/// The workspace living in the original `~/.scooby/dooby.db`.
+pub const DEFAULT_WORKSPACE: &str = "default";
+
+/// `[db]` in the config file.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct DbConfig {
+    /// database file, `~/` is expanded
+    pub path: Option<String>,
+    /// named workspace used when there's no `--workspace`
+    pub workspace: Option<String>,
+}
+
+/// Workspace names end up as file names, so keep them boring.
+pub fn parse_workspace(s: &str) -> Result<String, String> {
+    let name = s.trim();
+    if name.is_empty() {
+        return Err("workspace can't be empty".into());
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!(
+            "workspace `{name}` can only contain letters, digits, - and _"
+        ));
+    }
+    Ok(name.to_string())
+}
+
+fn home() -> Result<String, String> {
+    std::env::var("HOME").map_err(|err| format!("couldn't find HOME: {err}"))
+}
+
+fn expand_home(path: &str) -> Result<String, String> {
+    match path.strip_prefix("~/") {
+        Some(rest) => Ok(format!("{}/{}", home()?, rest)),
+        None => Ok(path.to_string()),
+    }
+}
+
+/// `~/.scooby/workspaces/<name>.db`, or the original db for `default`.
+pub fn workspace_path(name: &str) -> Result<String, String> {
+    let scooby_dir = format!("{}/{}", home()?, SCOOBY_DIR);
+    if name == DEFAULT_WORKSPACE {
+        return Ok(format!("{scooby_dir}/{DEFAULT_DB}"));
+    }
+    Ok(format!("{scooby_dir}/{WORKSPACES_DIR}/{name}.db"))
+}
+
+/// Where the history lives: `--db`, `--workspace`, `SCOOBY_DB`, then `path` and `workspace` from
+/// `[db]`, falling back to `~/.scooby/dooby.db`.
+pub fn resolve_db_path(
+    db: Option<String>,
+    workspace: Option<String>,
+    cfg: &Cfg,
+) -> Result<String, String> {
+    if let Some(path) = db {
+        return expand_home(&path);
+    }
+    if let Some(name) = workspace {
+        return workspace_path(&name);
+    }
+    if let Ok(path) = std::env::var(DB_ENV)
+        && !path.is_empty()
+    {
+        return expand_home(&path);
+    }
+    let config: DbConfig = cfg.get_section("db").unwrap_or_default();
+    if let Some(path) = config.path {
+        return expand_home(&path);
+    }
+    if let Some(name) = config.workspace {
+        let name = parse_workspace(&name).map_err(|err| format!("[db] {err}"))?;
+        return workspace_path(&name);
+    }
+    workspace_path(DEFAULT_WORKSPACE)
+}
+
+/// Names of the existing workspaces, `default` first.
+pub fn list_workspaces() -> Result<Vec<String>, String> {
+    let dir = format!("{}/{}/{}", home()?, SCOOBY_DIR, WORKSPACES_DIR);
+    let mut names = Vec::new();
+    if Path::new(&dir).exists() {
+        let entries =
+            std::fs::read_dir(&dir).map_err(|err| format!("couldn't read {dir}: {err}"))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "db")
+                && let Some(name) = path.file_stem().and_then(|stem| stem.to_str())
+            {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names.sort();
+    names.retain(|name| name != DEFAULT_WORKSPACE);
+    names.insert(0, DEFAULT_WORKSPACE.to_string());
+    Ok(names)
+}